@@ -0,0 +1,135 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::content::Registry;
+use crate::git as git_helpers;
+use crate::images;
+use crate::links;
+
+/// `colocated_assets`: where files kept next to a post, such as `post/diagram.svg`
+/// beside `post.md`, are published.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColocatedAssets {
+    /// Only the working images dir is used
+    #[default]
+    Off,
+    /// Into the collection's images dir, with the post's links rewritten
+    Images,
+    /// Next to the published post, keeping their relative paths
+    Content,
+}
+
+impl ColocatedAssets {
+    pub fn is_off(&self) -> bool {
+        *self == ColocatedAssets::Off
+    }
+}
+
+/// Non-content files the post at `post` links to by a relative path, as
+/// (link target as written, file). Each target is listed once.
+pub fn colocated(post: &Path, src: &str, registry: &Registry) -> Vec<(String, PathBuf)> {
+    let Some(dir) = post.parent() else {
+        return Vec::new();
+    };
+    let mut out: Vec<(String, PathBuf)> = Vec::new();
+    for (_, target, _) in links::extract_links(src, registry) {
+        let path = target.split(['#', '?']).next().unwrap_or_default();
+        if path.is_empty() || path.starts_with('/') || path.contains(':') {
+            continue;
+        }
+        let file = dir.join(path);
+        let is_content = registry.kind_of(&file).is_some_and(|k| k.is_content());
+        if !file.is_file() || is_content || out.iter().any(|(t, _)| *t == target) {
+            continue;
+        }
+        out.push((target, file));
+    }
+    out
+}
+
+/// Files in `images_dir`, served at `url`, that a published post links to, as
+/// (link target as written, file).
+pub fn linked_images(
+    src: &str,
+    images_dir: &Path,
+    url: &str,
+    registry: &Registry,
+) -> Vec<(String, PathBuf)> {
+    let mut out: Vec<(String, PathBuf)> = Vec::new();
+    for (_, target, _) in links::extract_links(src, registry) {
+        let path = target.split(['#', '?']).next().unwrap_or_default();
+        let Some(rest) = path.strip_prefix(url).and_then(|r| r.strip_prefix('/')) else {
+            continue;
+        };
+        let file = images_dir.join(rest);
+        if file.is_file() && !out.iter().any(|(t, _)| *t == target) {
+            out.push((target, file));
+        }
+    }
+    out
+}
+
+/// The URL a collection's images dir is served at: `your-site/public/images` ->
+/// `/images`, and an empty string for `public/` itself.
+pub fn public_url(images_dir: &Path, files: &Path) -> Result<String> {
+    let public = git_helpers::get_site_root(files).join("public");
+    let rel = images_dir.strip_prefix(&public).map_err(|_| {
+        anyhow::anyhow!(
+            "{} is not inside {}, so co-located assets can't be linked from there",
+            images_dir.display(),
+            public.display()
+        )
+    })?;
+    let rel = images::slash_path(rel);
+    Ok(if rel.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", rel)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn relative_links_to_files_are_colocated() {
+        let td = tempdir().unwrap();
+        fs::create_dir_all(td.path().join("post")).unwrap();
+        for name in ["post/diagram.svg", "post/slides.pdf", "other.md"] {
+            fs::write(td.path().join(name), b"x").unwrap();
+        }
+        let post = td.path().join("post.md");
+        let src = "---\ncover: ./post/diagram.svg\n---\n\
+![d](post/diagram.svg) [slides](post/slides.pdf#p2) [next](other.md)\n\
+![gone](post/missing.png) ![abs](/images/a.png) [web](https://example.com/x.pdf)\n";
+
+        let found = colocated(&post, src, &Registry::default());
+        let targets: Vec<&str> = found.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(
+            targets,
+            [
+                "./post/diagram.svg",
+                "post/diagram.svg",
+                "post/slides.pdf#p2"
+            ]
+        );
+        assert_eq!(found[2].1, td.path().join("post/slides.pdf"));
+    }
+
+    #[test]
+    fn images_dir_url_comes_from_public() {
+        let site = Path::new("/site");
+        let files = site.join("content/blog");
+        assert_eq!(
+            public_url(&site.join("public/images"), &files).unwrap(),
+            "/images"
+        );
+        assert_eq!(public_url(&site.join("public"), &files).unwrap(), "");
+        assert!(public_url(Path::new("/elsewhere"), &files).is_err());
+    }
+}
@@ -1,26 +1,147 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 use std::io::Write;
 use std::path::Path;
-use std::path::PathBuf;
+use std::path::{Component, PathBuf};
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::assets::ColocatedAssets;
+use crate::content::{Registry, TypesConfig};
+use crate::fs::FileFilter;
+use crate::images::OptimizeConfig;
+use crate::links;
+use crate::redirects::RedirectsConfig;
+use crate::schema::{Schema, SchemaConfig};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct WorkingConfig {
     pub files: String,
     pub images: Option<String>,
+    /// Glob patterns a file must match to be listed (default: every file)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Glob patterns for files to hide, e.g. `_*.md` or `README.md`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Allowed file extensions (default: every supported type)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub types: Option<Vec<String>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CollectionConfig {
     pub name: String,
     pub files: String,
     pub images: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub types: Option<Vec<String>>,
+    /// Route the collection is served under when it isn't its directory's,
+    /// as set by a `prefix` in `content.config.ts`, e.g. `/posts`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// Front-matter fields every published file must have, e.g. `["title", "date"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+    /// Front-matter fields with their types, or the path of a JSON Schema file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<SchemaConfig>,
+    /// Resize and re-encode images published to this collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimize: Option<OptimizeConfig>,
+    /// Strip EXIF/GPS and other metadata from every image published to this collection
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub scrub_metadata: bool,
+    /// Publish each post's images into `<images>/<slug>/` instead of side by side
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub per_post_images: bool,
+    /// Publish files the post links to next to it in the working dir
+    #[serde(default, skip_serializing_if = "ColocatedAssets::is_off")]
+    pub colocated_assets: ColocatedAssets,
+    /// Extensions of the images and other files published with a post (default: every known one)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assets: Option<Vec<String>>,
+    /// Directories for some asset types by extension, e.g. `mp4 = "your-site/public/video"`;
+    /// the rest go to `images`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub asset_dirs: BTreeMap<String, String>,
+}
+
+/// What relative paths in the config are relative to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RelativeTo {
+    #[default]
+    Home,
+    Config,
+}
+
+impl RelativeTo {
+    fn is_home(&self) -> bool {
+        *self == RelativeTo::Home
+    }
+}
+
+/// How the file picker orders its list.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// File path, A to Z
+    #[default]
+    Name,
+    /// Last modified first
+    Mtime,
+    /// Front-matter `date`, newest first
+    Date,
+    /// Front-matter `title`, A to Z
+    Title,
+}
+
+/// How the file picker groups its list.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    #[default]
+    None,
+    /// Directory relative to the listed dir
+    Dir,
+    /// Front-matter `tags`; a file with several tags is listed under each
+    Tag,
+}
+
+/// `[picker]` defaults; the `--sort`, `--group` and `--pin-recent` flags override them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PickerConfig {
+    pub sort: SortBy,
+    pub group: GroupBy,
+    /// Show this many of the most recently edited files at the top
+    pub pin_recent: usize,
+}
+
+impl PickerConfig {
+    fn is_default(&self) -> bool {
+        *self == PickerConfig::default()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
+    #[serde(default, skip_serializing_if = "RelativeTo::is_home")]
+    pub relative_to: RelativeTo,
     pub working: WorkingConfig,
+    /// Extra file extensions, grouped by what they hold
+    #[serde(default, skip_serializing_if = "TypesConfig::is_empty")]
+    pub types: TypesConfig,
+    #[serde(default, skip_serializing_if = "PickerConfig::is_default")]
+    pub picker: PickerConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirects: Option<RedirectsConfig>,
     #[serde(default)]
     pub collection: Vec<CollectionConfig>,
 }
@@ -30,6 +151,52 @@ pub struct CollectionPaths {
     pub name: String,
     pub files: PathBuf,
     pub images: Option<PathBuf>,
+    pub filter: FileFilter,
+    /// Route prefix overriding the one Nuxt derives from `files`
+    pub prefix: Option<String>,
+    pub schema: Schema,
+    pub optimize: Option<OptimizeConfig>,
+    pub scrub_metadata: bool,
+    pub per_post_images: bool,
+    pub colocated_assets: ColocatedAssets,
+    /// Lowercase extensions of the files published with a post
+    pub asset_types: Vec<String>,
+    pub asset_dirs: BTreeMap<String, PathBuf>,
+}
+
+impl CollectionPaths {
+    /// Directory below `images` holding the images of the post `stem`, when the
+    /// collection keeps one per post.
+    pub fn post_images_dir(&self, stem: &str) -> Option<String> {
+        self.per_post_images.then(|| links::slug(stem))
+    }
+
+    /// Whether files like `path` are published along with a post.
+    pub fn takes_asset(&self, path: &Path) -> bool {
+        crate::fs::has_type(path, &self.asset_types)
+    }
+
+    /// Directory an asset like `path` is published into: the one configured for
+    /// its extension, otherwise `images`.
+    pub fn asset_dir(&self, path: &Path) -> Option<&PathBuf> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        self.asset_dirs.get(&ext).or(self.images.as_ref())
+    }
+
+    /// Every directory assets are published into, with the extensions it takes.
+    pub fn asset_roots(&self) -> Vec<(&PathBuf, Vec<String>)> {
+        let mut roots: Vec<(&PathBuf, Vec<String>)> = Vec::new();
+        for ext in &self.asset_types {
+            let Some(dir) = self.asset_dirs.get(ext).or(self.images.as_ref()) else {
+                continue;
+            };
+            match roots.iter_mut().find(|(d, _)| *d == dir) {
+                Some((_, exts)) => exts.push(ext.clone()),
+                None => roots.push((dir, vec![ext.clone()])),
+            }
+        }
+        roots
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -37,6 +204,13 @@ pub struct AppPaths {
     // working area (local drafts)
     pub working_files: PathBuf,
     pub working_images: Option<PathBuf>,
+    pub working_filter: FileFilter,
+    // every known file type, including the ones added in [types]
+    pub registry: Registry,
+    // file picker order
+    pub picker: PickerConfig,
+    // redirects file kept by delete and move
+    pub redirects: Option<RedirectsConfig>,
     // collections (publishing targets)
     pub collections: Vec<CollectionPaths>,
 }
@@ -85,35 +259,181 @@ pub fn config_file_path() -> Option<PathBuf> {
     None
 }
 
-#[cfg(windows)]
-pub fn resolve_dir(dir: &str) -> PathBuf {
-    let p = Path::new(dir);
-    if p.is_absolute() {
-        p.to_path_buf()
-    } else {
-        // On Windows, use USERPROFILE as the home directory
-        let home = std::env::var("USERPROFILE").expect("USERPROFILE environment variable not set");
-        Path::new(&home).join(dir)
+/// Path of a named profile's config file, stored next to the default config as
+/// `profiles/<name>.toml`.
+pub fn profile_file_path(config_dir: &Path, name: &str) -> Result<PathBuf> {
+    let valid = !name.trim().is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !name.starts_with('.');
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid profile name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        ));
+    }
+    Ok(config_dir.join("profiles").join(format!("{}.toml", name)))
+}
+
+/// Pick the config file to use. An explicit path (`--config-path` or `NUCH_CONFIG`)
+/// wins over a named profile, which wins over the default location.
+/// Returns Ok(None) if no config directory can be determined on this platform.
+pub fn locate_config(explicit: Option<PathBuf>, profile: Option<&str>) -> Result<Option<PathBuf>> {
+    if let Some(p) = explicit {
+        return Ok(Some(p));
+    }
+    let Some(default) = config_file_path() else {
+        return Ok(None);
+    };
+    match profile {
+        Some(name) => Ok(Some(profile_file_path(default.parent().unwrap(), name)?)),
+        None => Ok(Some(default)),
     }
 }
 
+#[cfg(windows)]
+fn home_dir() -> Option<PathBuf> {
+    // On Windows, use USERPROFILE as the home directory
+    std::env::var_os("USERPROFILE").map(PathBuf::from)
+}
+
 #[cfg(not(windows))]
-pub fn resolve_dir(dir: &str) -> PathBuf {
-    let p = Path::new(dir);
+fn home_dir() -> Option<PathBuf> {
+    // On Unix-like systems, use HOME environment variable
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+/// Directory that relative paths in `cfg` are resolved against, or None for `$HOME`.
+pub fn relative_base(cfg: &Config, config_path: &Path) -> Option<PathBuf> {
+    match cfg.relative_to {
+        RelativeTo::Home => None,
+        RelativeTo::Config => Some(
+            config_path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+                .to_path_buf(),
+        ),
+    }
+}
+
+/// Expand `~` and `$VAR`/`${VAR}` in `dir`, then resolve a relative result against
+/// `relative_base` (the config directory), or the home directory when that is None.
+/// `.` and `..` are resolved lexically, so `site/../site/content` is `site/content`.
+pub fn resolve_dir(dir: &str, relative_base: Option<&Path>) -> Result<PathBuf> {
+    resolve_dir_with(dir, relative_base, home_dir().as_deref(), |name| {
+        std::env::var(name).ok()
+    })
+}
+
+fn resolve_dir_with(
+    dir: &str,
+    relative_base: Option<&Path>,
+    home: Option<&Path>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<PathBuf> {
+    joined_dir(dir, relative_base, home, env).map(|p| normalize_path(&p))
+}
+
+fn joined_dir(
+    dir: &str,
+    relative_base: Option<&Path>,
+    home: Option<&Path>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<PathBuf> {
+    let no_home = || anyhow::anyhow!("Cannot resolve '{}': home directory is not set", dir);
+    let expanded = expand_vars(dir, env)?;
+
+    if expanded == "~" {
+        return home.map(Path::to_path_buf).ok_or_else(no_home);
+    }
+    if let Some(rest) = expanded
+        .strip_prefix("~/")
+        .or_else(|| expanded.strip_prefix("~\\"))
+    {
+        return Ok(home.ok_or_else(no_home)?.join(rest));
+    }
+
+    let p = Path::new(&expanded);
     if p.is_absolute() {
-        p.to_path_buf()
-    } else {
-        // On Unix-like systems, use HOME environment variable
-        let home = std::env::var("HOME").expect("HOME environment variable not set");
-        Path::new(&home).join(dir)
+        return Ok(p.to_path_buf());
+    }
+    match relative_base {
+        Some(base) => Ok(base.join(p)),
+        None => Ok(home.ok_or_else(no_home)?.join(p)),
     }
 }
 
-/// Load, validate, and (optionally) generate the config file.
+/// Drop `.` segments and fold `..` into the segment before it, without touching
+/// the file system. A `..` above the root stays at the root.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            c => out.push(c),
+        }
+    }
+    if out.as_os_str().is_empty() {
+        out.push(".");
+    }
+    out
+}
+
+/// Replace `$VAR` and `${VAR}` with their values; unset variables are an error.
+fn expand_vars(s: &str, env: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(at) = rest.find('$') {
+        out.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| anyhow::anyhow!("Unclosed '${{' in path '{}'", s))?;
+            (&braced[..end], end + 2)
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+
+        if name.is_empty() {
+            // a lone '$' is kept as-is
+            out.push('$');
+            rest = after;
+            continue;
+        }
+        let value = env(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Environment variable '{}' used in path '{}' is not set",
+                name,
+                s
+            )
+        })?;
+        out.push_str(&value);
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Load, validate, and (optionally) generate the config file at `config_path`
+/// (see [`locate_config`]).
 /// Returns Ok(None) if `generate` was true and a sample config was written (caller should exit).
 /// Returns Ok(Some(AppPaths)) when config is present, parsed and validated.
-pub fn load_config(generate: bool) -> Result<Option<AppPaths>> {
-    let config_path = match config_file_path() {
+pub fn load_config(generate: bool, config_path: Option<PathBuf>) -> Result<Option<AppPaths>> {
+    let config_path = match config_path {
         Some(p) => p,
         None => {
             if generate {
@@ -121,42 +441,45 @@ pub fn load_config(generate: bool) -> Result<Option<AppPaths>> {
                 return Ok(None);
             } else {
                 return Err(anyhow::anyhow!(
-                    "Could not determine config directory on this platform. Use --config to create a sample."
+                    "Could not determine config directory on this platform. Use --config-path to point at a config file."
                 ));
             }
         }
     };
 
-    let config_dir = config_path.parent().unwrap();
-
     if generate {
-        fs::create_dir_all(config_dir)?;
         if config_path.exists() {
             println!("Config already exists at {}", config_path.display());
         } else {
             // sample config
             let sample = Config {
+                relative_to: RelativeTo::Home,
+                types: TypesConfig::default(),
+                picker: PickerConfig::default(),
+                redirects: None,
                 working: WorkingConfig {
                     files: "Documents/writings".to_string(),
                     images: Some("Documents/writings/images".to_string()),
+                    ..Default::default()
                 },
                 collection: vec![
                     CollectionConfig {
                         name: "writing".to_string(),
                         files: "your-site/content".to_string(),
                         images: Some("your-site/public/images".to_string()),
+                        ..Default::default()
                     },
                     CollectionConfig {
                         name: "blogs".to_string(),
                         files: "your-site/content/blogs".to_string(),
                         images: None,
+                        ..Default::default()
                     },
                 ],
             };
-            let toml_str = toml::to_string_pretty(&sample)?;
-            let mut f = fs::File::create(&config_path)?;
-            f.write_all(toml_str.as_bytes())?;
+            write_config(&sample, &config_path)?;
             println!("Wrote sample config to {}", config_path.display());
+            println!("Edit it to match your site, or run `nuch init` to build one interactively.");
         }
         return Ok(None);
     }
@@ -168,38 +491,144 @@ pub fn load_config(generate: bool) -> Result<Option<AppPaths>> {
         ));
     }
 
-    let s = fs::read_to_string(&config_path)
-        .map_err(|e| anyhow::anyhow!("Failed to read config {}: {}", config_path.display(), e))?;
-    let cfg: Config = toml::from_str(&s)
-        .map_err(|e| anyhow::anyhow!("Failed to parse config {}: {}", config_path.display(), e))?;
+    let cfg = read_config(&config_path)?;
+    validate_config(&cfg, &config_path).map(Some)
+}
 
-    // Validate working section
-    if cfg.working.files.trim().is_empty() {
-        return Err(anyhow::anyhow!("'working.files' in config is empty."));
+/// Read and parse the config file at `path` without validating it.
+pub fn read_config(path: &Path) -> Result<Config> {
+    let s = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read config {}: {}", path.display(), e))?;
+    toml::from_str(&s)
+        .map_err(|e| anyhow::anyhow!("Failed to parse config {}: {}", path.display(), e))
+}
+
+/// A single problem found in the config, with a suggestion on how to fix it.
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub message: String,
+    pub hint: String,
+}
+
+impl ConfigIssue {
+    fn new(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            hint: hint.into(),
+        }
     }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n    hint: {}", self.message, self.hint)
+    }
+}
+
+/// Check that every configured directory exists and resolve them into `AppPaths`.
+/// `config_path` is where the config lives (or will be written).
+pub fn validate_config(cfg: &Config, config_path: &Path) -> Result<AppPaths> {
+    let (paths, issues) = check_config(cfg, config_path);
+    if !issues.is_empty() {
+        let lines: Vec<String> = issues.iter().map(|i| format!("  - {}", i)).collect();
+        return Err(anyhow::anyhow!("Invalid config:\n{}", lines.join("\n")));
+    }
+    Ok(paths)
+}
+
+/// Resolve all configured paths and collect every problem found along the way.
+/// Collections with an empty or duplicate name are left out of the returned paths.
+pub fn check_config(cfg: &Config, config_path: &Path) -> (AppPaths, Vec<ConfigIssue>) {
+    let mut errs: Vec<ConfigIssue> = Vec::new();
+    let base = relative_base(cfg, config_path);
+
+    // Resolve a configured path, recording a problem and keeping it verbatim on failure
+    let resolve = |field: &str, dir: &str, errs: &mut Vec<ConfigIssue>| {
+        resolve_dir(dir, base.as_deref()).unwrap_or_else(|e| {
+            errs.push(ConfigIssue::new(
+                format!("{}: {}", field, e),
+                "set the variable, or use an absolute path",
+            ));
+            PathBuf::from(dir)
+        })
+    };
 
     // Resolve working paths
-    let working_files_path = resolve_dir(&cfg.working.files);
-    let working_images_path = cfg.working.images.as_ref().map(|s| resolve_dir(s));
+    let working_files_path = resolve("working.files", &cfg.working.files, &mut errs);
+    let working_images_path = cfg
+        .working
+        .images
+        .as_ref()
+        .map(|s| resolve("working.images", s, &mut errs));
+
+    let registry = Registry::with_config(&cfg.types).unwrap_or_else(|e| {
+        errs.push(ConfigIssue::new(
+            format!("types: {}", e),
+            "each extension may appear once, under the kind of content it holds",
+        ));
+        Registry::default()
+    });
+
+    // Build a file filter, recording a problem and falling back to the default on failure
+    let filter = |field: &str,
+                  include: &[String],
+                  exclude: &[String],
+                  types: Option<&[String]>,
+                  errs: &mut Vec<ConfigIssue>| {
+        FileFilter::new(include, exclude, types, &registry).unwrap_or_else(|e| {
+            errs.push(ConfigIssue::new(
+                format!("{}: {}", field, e),
+                "fix the include/exclude patterns (see https://docs.rs/globset) or the types list",
+            ));
+            FileFilter::new(&[], &[], None, &registry).unwrap_or_default()
+        })
+    };
+
+    let working_filter = filter(
+        "working",
+        &cfg.working.include,
+        &cfg.working.exclude,
+        cfg.working.types.as_deref(),
+        &mut errs,
+    );
 
     // Validate working dir exists and contains markdown
-    let mut errs: Vec<String> = Vec::new();
-    if !working_files_path.is_dir() {
-        errs.push(format!(
-            "working.files does not exist or is not a directory: {}",
-            working_files_path.display()
+    if cfg.working.files.trim().is_empty() {
+        errs.push(ConfigIssue::new(
+            "'working.files' in config is empty",
+            "set [working] files to the directory holding your drafts",
+        ));
+    } else if !working_files_path.is_dir() {
+        errs.push(ConfigIssue::new(
+            format!(
+                "working.files does not exist or is not a directory: {}",
+                working_files_path.display()
+            ),
+            "create the directory or fix the path in [working] files",
         ));
     } else {
-        match super::fs::dir_has_supported_files(&working_files_path) {
+        match super::fs::dir_has_supported_files(&working_files_path, &working_filter) {
             Ok(true) => {}
-            Ok(false) => errs.push(format!(
-                "No supported files (.md, .yaml, .yml, .json, .csv) found in working.files: {}",
-                working_files_path.display()
+            Ok(false) => errs.push(ConfigIssue::new(
+                format!(
+                    "No supported files ({}) found in working.files: {}",
+                    working_filter
+                        .types()
+                        .iter()
+                        .map(|t| format!(".{}", t))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    working_files_path.display()
+                ),
+                "add at least one draft, point [working] files at your drafts directory, or relax [working] include/exclude/types",
             )),
-            Err(e) => errs.push(format!(
-                "Failed to read working.files {}: {}",
-                working_files_path.display(),
-                e
+            Err(e) => errs.push(ConfigIssue::new(
+                format!(
+                    "Failed to read working.files {}: {}",
+                    working_files_path.display(),
+                    e
+                ),
+                "check the directory permissions",
             )),
         }
     }
@@ -207,9 +636,12 @@ pub fn load_config(generate: bool) -> Result<Option<AppPaths>> {
     if let Some(p) = &working_images_path
         && !p.is_dir()
     {
-        errs.push(format!(
-            "working.images does not exist or is not a directory: {}",
-            p.display()
+        errs.push(ConfigIssue::new(
+            format!(
+                "working.images does not exist or is not a directory: {}",
+                p.display()
+            ),
+            "create the directory, fix the path, or remove [working] images",
         ));
     }
 
@@ -219,37 +651,153 @@ pub fn load_config(generate: bool) -> Result<Option<AppPaths>> {
 
     for col in &cfg.collection {
         if col.name.trim().is_empty() {
-            errs.push("A collection has an empty 'name' field".to_string());
+            errs.push(ConfigIssue::new(
+                "A collection has an empty 'name' field",
+                "give every [[collection]] a unique name",
+            ));
             continue;
         }
         if !seen_names.insert(col.name.clone()) {
-            errs.push(format!("Duplicate collection name: {}", col.name));
+            errs.push(ConfigIssue::new(
+                format!("Duplicate collection name: {}", col.name),
+                "rename or remove one of the collections (`nuch config remove-collection`)",
+            ));
             continue;
         }
 
         if col.files.trim().is_empty() {
-            errs.push(format!("Collection '{}' has empty 'files' path", col.name));
+            errs.push(ConfigIssue::new(
+                format!("Collection '{}' has empty 'files' path", col.name),
+                "set files to the collection directory, usually inside content/",
+            ));
             continue;
         }
 
-        let files_path = resolve_dir(&col.files);
-        let images_path = col.images.as_ref().map(|s| resolve_dir(s));
+        let files_path = resolve(
+            &format!("collection '{}' files", col.name),
+            &col.files,
+            &mut errs,
+        );
+        let images_path = col
+            .images
+            .as_ref()
+            .map(|s| resolve(&format!("collection '{}' images", col.name), s, &mut errs));
 
         if !files_path.is_dir() {
-            errs.push(format!(
-                "Collection '{}' files path does not exist or is not a directory: {}",
-                col.name,
-                files_path.display()
+            errs.push(ConfigIssue::new(
+                format!(
+                    "Collection '{}' files path does not exist or is not a directory: {}",
+                    col.name,
+                    files_path.display()
+                ),
+                "create the directory or fix the collection's files path",
             ));
         }
 
         if let Some(p) = &images_path
             && !p.is_dir()
         {
-            errs.push(format!(
-                "Collection '{}' images path does not exist or is not a directory: {}",
-                col.name,
-                p.display()
+            errs.push(ConfigIssue::new(
+                format!(
+                    "Collection '{}' images path does not exist or is not a directory: {}",
+                    col.name,
+                    p.display()
+                ),
+                "create the directory, fix the path, or remove the collection's images entry",
+            ));
+        }
+
+        let col_filter = filter(
+            &format!("collection '{}'", col.name),
+            &col.include,
+            &col.exclude,
+            col.types.as_deref(),
+            &mut errs,
+        );
+
+        let mut schema = match &col.schema {
+            Some(s) => Schema::from_config(s, base.as_deref()).unwrap_or_else(|e| {
+                errs.push(ConfigIssue::new(
+                    format!("collection '{}' schema: {}", col.name, e),
+                    "fix the schema fields, or point schema at a JSON Schema file",
+                ));
+                Schema::default()
+            }),
+            None => Schema::default(),
+        };
+        schema.require(&col.required);
+
+        if let Some(p) = &col.prefix
+            && !p.starts_with('/')
+        {
+            errs.push(ConfigIssue::new(
+                format!("collection '{}' prefix '{}' is not a route", col.name, p),
+                "start the prefix with '/', e.g. \"/posts\"",
+            ));
+        }
+
+        if let Some(o) = &col.optimize
+            && !(1..=100).contains(&o.quality)
+        {
+            errs.push(ConfigIssue::new(
+                format!(
+                    "collection '{}' optimize.quality is {}",
+                    col.name, o.quality
+                ),
+                "use a quality between 1 and 100",
+            ));
+        }
+
+        let asset_types: Vec<String> = match &col.assets {
+            Some(types) => types
+                .iter()
+                .map(|t| t.trim().trim_start_matches('.').to_lowercase())
+                .filter(|ext| {
+                    let known = registry.kind_of_ext(ext).is_some_and(|k| !k.is_content());
+                    if !known {
+                        errs.push(ConfigIssue::new(
+                            format!("collection '{}' assets: unknown type '{}'", col.name, ext),
+                            "list image or asset extensions, adding new ones to [types] images or assets",
+                        ));
+                    }
+                    known
+                })
+                .collect(),
+            None => registry.asset_exts(),
+        };
+
+        let mut asset_dirs = BTreeMap::new();
+        for (ext, dir) in &col.asset_dirs {
+            let ext = ext.trim_start_matches('.').to_lowercase();
+            let field = format!("collection '{}' asset_dirs.{}", col.name, ext);
+            if !asset_types.contains(&ext) {
+                errs.push(ConfigIssue::new(
+                    format!("{}: '{}' is not one of the collection's assets", field, ext),
+                    "add the extension to the collection's assets, or remove the entry",
+                ));
+                continue;
+            }
+            let path = resolve(&field, dir, &mut errs);
+            if !path.is_dir() {
+                errs.push(ConfigIssue::new(
+                    format!(
+                        "{} does not exist or is not a directory: {}",
+                        field,
+                        path.display()
+                    ),
+                    "create the directory or fix the path",
+                ));
+            }
+            asset_dirs.insert(ext, path);
+        }
+
+        if col.colocated_assets == ColocatedAssets::Images && images_path.is_none() {
+            errs.push(ConfigIssue::new(
+                format!(
+                    "collection '{}' publishes co-located assets to images, but has no images dir",
+                    col.name
+                ),
+                "set the collection's images dir, or use colocated_assets = \"content\"",
             ));
         }
 
@@ -257,16 +805,226 @@ pub fn load_config(generate: bool) -> Result<Option<AppPaths>> {
             name: col.name.clone(),
             files: files_path,
             images: images_path,
+            filter: col_filter,
+            prefix: col.prefix.clone(),
+            schema,
+            optimize: col.optimize.clone(),
+            scrub_metadata: col.scrub_metadata,
+            per_post_images: col.per_post_images,
+            colocated_assets: col.colocated_assets,
+            asset_types,
+            asset_dirs,
         });
     }
 
-    if !errs.is_empty() {
-        return Err(anyhow::anyhow!(errs.join("; ")));
-    }
-
-    Ok(Some(AppPaths {
+    let paths = AppPaths {
         working_files: working_files_path,
         working_images: working_images_path,
+        working_filter,
+        registry,
+        picker: cfg.picker.clone(),
+        redirects: cfg.redirects.clone(),
         collections: collection_paths,
-    }))
+    };
+    (paths, errs)
+}
+
+/// Serialize `cfg` and write it to `path`, creating parent directories as needed.
+pub fn write_config(cfg: &Config, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        fs::create_dir_all(dir)?;
+    }
+    let toml_str = toml::to_string_pretty(cfg)?;
+    let mut f = fs::File::create(path)?;
+    f.write_all(toml_str.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_file_path_uses_profiles_dir() {
+        let base = Path::new("/cfg/nuch");
+        assert_eq!(
+            profile_file_path(base, "docs").unwrap(),
+            PathBuf::from("/cfg/nuch/profiles/docs.toml")
+        );
+        assert!(profile_file_path(base, "").is_err());
+        assert!(profile_file_path(base, "../evil").is_err());
+        assert!(profile_file_path(base, "a/b").is_err());
+    }
+
+    #[cfg(not(windows))]
+    mod resolve {
+        use super::*;
+
+        fn env(name: &str) -> Option<String> {
+            match name {
+                "SITE" => Some("/srv/site".to_string()),
+                "USER_DIR" => Some("alice".to_string()),
+                _ => None,
+            }
+        }
+
+        fn resolve(dir: &str, base: Option<&str>) -> Result<PathBuf> {
+            resolve_dir_with(dir, base.map(Path::new), Some(Path::new("/home/me")), env)
+        }
+
+        #[test]
+        fn absolute_paths_are_kept() {
+            assert_eq!(resolve("/srv/x", None).unwrap(), PathBuf::from("/srv/x"));
+            assert_eq!(
+                resolve("/srv/x", Some("/cfg")).unwrap(),
+                PathBuf::from("/srv/x")
+            );
+        }
+
+        #[test]
+        fn tilde_expands_to_home() {
+            assert_eq!(resolve("~", None).unwrap(), PathBuf::from("/home/me"));
+            assert_eq!(
+                resolve("~/notes", Some("/cfg")).unwrap(),
+                PathBuf::from("/home/me/notes")
+            );
+            // only a leading tilde is special
+            assert_eq!(
+                resolve("a/~/b", None).unwrap(),
+                PathBuf::from("/home/me/a/~/b")
+            );
+        }
+
+        #[test]
+        fn env_vars_are_expanded() {
+            assert_eq!(
+                resolve("${SITE}/content", None).unwrap(),
+                PathBuf::from("/srv/site/content")
+            );
+            assert_eq!(
+                resolve("$SITE/public", None).unwrap(),
+                PathBuf::from("/srv/site/public")
+            );
+            assert_eq!(
+                resolve("writing/$USER_DIR-drafts", None).unwrap(),
+                PathBuf::from("/home/me/writing/alice-drafts")
+            );
+            assert!(resolve("$MISSING/x", None).is_err());
+            assert!(resolve("${SITE/x", None).is_err());
+        }
+
+        #[test]
+        fn relative_paths_use_base_or_home() {
+            assert_eq!(
+                resolve("Documents/w", None).unwrap(),
+                PathBuf::from("/home/me/Documents/w")
+            );
+            assert_eq!(
+                resolve("../site/content", Some("/cfg/nuch")).unwrap(),
+                PathBuf::from("/cfg/site/content")
+            );
+        }
+
+        #[test]
+        fn dot_segments_are_resolved() {
+            // as compared by `config sync`, with a config dir next to the site
+            assert_eq!(
+                resolve("site/../site/./content/blog", Some("/cfg")).unwrap(),
+                PathBuf::from("/cfg/site/content/blog")
+            );
+            assert_eq!(
+                resolve("../../..", Some("/cfg/nuch")).unwrap(),
+                PathBuf::from("/")
+            );
+            assert_eq!(resolve("site", Some(".")).unwrap(), PathBuf::from("site"));
+            assert_eq!(
+                resolve("../site", Some(".")).unwrap(),
+                PathBuf::from("../site")
+            );
+        }
+
+        #[test]
+        fn missing_home_is_an_error() {
+            assert!(resolve_dir_with("~/x", None, None, env).is_err());
+            assert!(resolve_dir_with("x", None, None, env).is_err());
+            assert_eq!(
+                resolve_dir_with("x", Some(Path::new("/cfg")), None, env).unwrap(),
+                PathBuf::from("/cfg/x")
+            );
+        }
+
+        #[test]
+        fn relative_to_config_uses_config_dir() {
+            let cfg: Config =
+                toml::from_str("relative_to = \"config\"\n[working]\nfiles = \"drafts\"\n")
+                    .unwrap();
+            assert_eq!(
+                relative_base(&cfg, Path::new("/cfg/nuch/site.toml")),
+                Some(PathBuf::from("/cfg/nuch"))
+            );
+
+            let cfg: Config = toml::from_str("[working]\nfiles = \"drafts\"\n").unwrap();
+            assert_eq!(relative_base(&cfg, Path::new("/cfg/nuch/site.toml")), None);
+        }
+    }
+
+    #[test]
+    fn assets_are_routed_to_their_dirs() {
+        let td = tempfile::tempdir().unwrap();
+        for dir in [
+            "work",
+            "site/content/blog",
+            "site/public/images",
+            "site/public/video",
+        ] {
+            fs::create_dir_all(td.path().join(dir)).unwrap();
+        }
+        let cfg: Config = toml::from_str(
+            r#"relative_to = "config"
+[working]
+files = "work"
+[[collection]]
+name = "blog"
+files = "site/content/blog"
+images = "site/public/images"
+assets = ["png", "MP4", "webm"]
+[collection.asset_dirs]
+mp4 = "site/public/video"
+webm = "site/public/missing"
+pdf = "site/public/docs"
+"#,
+        )
+        .unwrap();
+        let (paths, issues) = check_config(&cfg, &td.path().join("nuch.toml"));
+        let messages: Vec<&str> = issues
+            .iter()
+            .map(|i| i.message.as_str())
+            .filter(|m| m.contains("asset_dirs"))
+            .collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].contains("asset_dirs.pdf: 'pdf' is not one of"));
+        assert!(messages[1].contains("asset_dirs.webm does not exist"));
+
+        let blog = &paths.collections[0];
+        let images = td.path().join("site/public/images");
+        let video = td.path().join("site/public/video");
+        assert!(blog.takes_asset(Path::new("a.mp4")));
+        assert!(!blog.takes_asset(Path::new("a.pdf")));
+        assert_eq!(blog.asset_dir(Path::new("clip.MP4")), Some(&video));
+        assert_eq!(blog.asset_dir(Path::new("cover.png")), Some(&images));
+        let roots: Vec<(&PathBuf, Vec<String>)> = blog.asset_roots();
+        assert_eq!(roots[0], (&images, vec!["png".to_string()]));
+        assert_eq!(roots[1], (&video, vec!["mp4".to_string()]));
+    }
+
+    #[test]
+    fn locate_config_prefers_explicit_path() {
+        let explicit = PathBuf::from("/tmp/site-a.toml");
+        assert_eq!(
+            locate_config(Some(explicit.clone()), Some("docs")).unwrap(),
+            Some(explicit)
+        );
+    }
 }
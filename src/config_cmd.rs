@@ -0,0 +1,339 @@
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use toml_edit::{DocumentMut, Item, Table, value};
+
+use crate::config::{self, AppPaths, Config};
+use crate::git as git_helpers;
+use crate::nuxt;
+
+/// Print the config file location and every path after resolution.
+pub fn show(config_path: &Path) -> Result<()> {
+    let cfg = config::read_config(config_path)?;
+    let (paths, issues) = config::check_config(&cfg, config_path);
+
+    println!("Config: {}\n", config_path.display());
+    println!("[working]");
+    println!("  files  = {}", describe(&paths.working_files));
+    if let Some(p) = &paths.working_images {
+        println!("  images = {}", describe(p));
+    }
+    for c in &paths.collections {
+        println!("\n[collection {}]", c.name);
+        println!("  files  = {}", describe(&c.files));
+        if let Some(p) = &c.images {
+            println!("  images = {}", describe(p));
+        }
+    }
+
+    if !issues.is_empty() {
+        println!(
+            "\n{} problem(s) found; run `nuch config validate` for details.",
+            issues.len()
+        );
+    }
+    Ok(())
+}
+
+/// Report every config problem on its own line with a hint on how to fix it.
+pub fn validate(config_path: &Path) -> Result<()> {
+    let cfg = config::read_config(config_path)?;
+    let (_, issues) = config::check_config(&cfg, config_path);
+    if issues.is_empty() {
+        println!("{} is valid.", config_path.display());
+        return Ok(());
+    }
+
+    for issue in &issues {
+        println!("error: {}", issue.message);
+        println!("  hint: {}", issue.hint);
+    }
+    Err(anyhow::anyhow!(
+        "{} problem(s) found in {}",
+        issues.len(),
+        config_path.display()
+    ))
+}
+
+/// Open the config in `$VISUAL`/`$EDITOR` and validate it afterwards.
+pub fn edit(config_path: &Path) -> Result<()> {
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!(
+            "Config file not found at {}. Run `nuch init` or `nuch --config` to create one.",
+            config_path.display()
+        ));
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+    // $EDITOR may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("$EDITOR is empty"))?;
+    let status = Command::new(program)
+        .args(parts)
+        .arg(config_path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to start editor '{}': {}", editor, e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Editor '{}' exited with {}",
+            editor,
+            status
+        ));
+    }
+
+    validate(config_path)
+}
+
+/// Append a `[[collection]]` entry, keeping the rest of the file untouched.
+pub fn add_collection(
+    config_path: &Path,
+    name: &str,
+    files: &str,
+    images: Option<&str>,
+) -> Result<()> {
+    let src = fs::read_to_string(config_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read config {}: {}", config_path.display(), e))?;
+    let updated = with_collection_added(&src, name, files, images)?;
+    fs::write(config_path, updated)?;
+    println!("Added collection '{}' to {}", name, config_path.display());
+
+    let cfg = config::read_config(config_path)?;
+    let resolved = config::resolve_dir(files, config::relative_base(&cfg, config_path).as_deref())?;
+    if !resolved.is_dir() {
+        println!("Warning: {} does not exist yet", resolved.display());
+    }
+    Ok(())
+}
+
+/// Remove the `[[collection]]` entry called `name`, keeping the rest of the file untouched.
+pub fn remove_collection(config_path: &Path, name: &str) -> Result<()> {
+    let src = fs::read_to_string(config_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read config {}: {}", config_path.display(), e))?;
+    let updated = with_collection_removed(&src, name)?;
+    fs::write(config_path, updated)?;
+    println!(
+        "Removed collection '{}' from {}",
+        name,
+        config_path.display()
+    );
+    Ok(())
+}
+
+fn with_collection_added(
+    src: &str,
+    name: &str,
+    files: &str,
+    images: Option<&str>,
+) -> Result<String> {
+    if name.trim().is_empty() || files.trim().is_empty() {
+        return Err(anyhow::anyhow!(
+            "Collection name and files must not be empty"
+        ));
+    }
+    let mut doc: DocumentMut = src.parse()?;
+    let entry = doc
+        .entry("collection")
+        .or_insert(Item::ArrayOfTables(Default::default()));
+    let tables = entry
+        .as_array_of_tables_mut()
+        .ok_or_else(|| anyhow::anyhow!("'collection' in config is not a [[collection]] list"))?;
+    if tables
+        .iter()
+        .any(|t| t.get("name").and_then(|n| n.as_str()) == Some(name))
+    {
+        return Err(anyhow::anyhow!("Collection '{}' already exists", name));
+    }
+
+    let mut table = Table::new();
+    table.insert("name", value(name));
+    table.insert("files", value(files));
+    if let Some(images) = images {
+        table.insert("images", value(images));
+    }
+    tables.push(table);
+    Ok(doc.to_string())
+}
+
+fn with_collection_removed(src: &str, name: &str) -> Result<String> {
+    let mut doc: DocumentMut = src.parse()?;
+    let tables = doc
+        .get_mut("collection")
+        .and_then(|c| c.as_array_of_tables_mut())
+        .ok_or_else(|| anyhow::anyhow!("No collections in config"))?;
+    let before = tables.len();
+    tables.retain(|t| t.get("name").and_then(|n| n.as_str()) != Some(name));
+    if tables.len() == before {
+        return Err(anyhow::anyhow!("Collection '{}' not found", name));
+    }
+    Ok(doc.to_string())
+}
+
+fn describe(p: &Path) -> String {
+    if p.exists() {
+        p.display().to_string()
+    } else {
+        format!("{} (missing)", p.display())
+    }
+}
+
+/// Compare the collections in `content.config.*` with the `[[collection]]` entries
+/// in the nuch config and print the differences.
+pub fn sync(cfg: &Config, app_paths: &AppPaths, site: Option<PathBuf>) -> Result<()> {
+    let site_root = match site {
+        Some(s) => config::normalize_path(&std::env::current_dir()?.join(s)),
+        None => app_paths
+            .collections
+            .first()
+            .map(|c| git_helpers::get_site_root(&c.files))
+            .ok_or_else(|| anyhow::anyhow!("No collections configured; pass --site"))?,
+    };
+
+    let Some((cfg_file, derived)) = nuxt::load_site_collections(&site_root)? else {
+        return Err(anyhow::anyhow!(
+            "No content.config.ts/.js found in {}",
+            site_root.display()
+        ));
+    };
+    println!("Comparing {} with nuch config\n", cfg_file.display());
+
+    let mut differences = 0;
+    for d in &derived {
+        let globs = d.include.join(", ");
+        let by_name = app_paths.collections.iter().find(|c| c.name == d.name);
+        let by_dir = app_paths.collections.iter().find(|c| c.files == d.files);
+        match (by_name, by_dir) {
+            (Some(c), _) if c.files == d.files => {
+                let raw = cfg.collection.iter().find(|r| r.name == c.name);
+                let (include, exclude) = raw
+                    .map(|r| (r.include.clone(), r.exclude.clone()))
+                    .unwrap_or_default();
+                let same_patterns = include == d.include && exclude == d.exclude;
+                if !same_patterns {
+                    differences += 1;
+                    println!(
+                        "~ {}: patterns differ\n    nuch: include {:?}, exclude {:?}\n    nuxt: include {:?}, exclude {:?}",
+                        d.name, include, exclude, d.include, d.exclude
+                    );
+                }
+                if c.prefix != d.prefix {
+                    differences += 1;
+                    println!(
+                        "~ {}: prefix differs\n    nuch: {}\n    nuxt: {}",
+                        d.name,
+                        c.prefix.as_deref().unwrap_or("(none)"),
+                        d.prefix.as_deref().unwrap_or("(none)")
+                    );
+                } else if same_patterns {
+                    println!("= {} ({}) [{}]", d.name, d.files.display(), globs);
+                }
+            }
+            (Some(c), _) => {
+                differences += 1;
+                println!(
+                    "~ {}: files differ\n    nuch: {}\n    nuxt: {}",
+                    d.name,
+                    c.files.display(),
+                    d.files.display()
+                );
+            }
+            (None, Some(c)) => {
+                differences += 1;
+                println!(
+                    "~ {}: named '{}' in nuch config ({})",
+                    d.name,
+                    c.name,
+                    d.files.display()
+                );
+            }
+            (None, None) => {
+                differences += 1;
+                println!(
+                    "+ {}: missing from nuch config ({}) [{}]\n    [[collection]]\n    name = \"{}\"\n    files = \"{}\"\n    include = {:?}",
+                    d.name,
+                    d.files.display(),
+                    globs,
+                    d.name,
+                    d.files.display(),
+                    d.include
+                );
+                if !d.exclude.is_empty() {
+                    println!("    exclude = {:?}", d.exclude);
+                }
+                if let Some(prefix) = &d.prefix {
+                    println!("    prefix = {:?}", prefix);
+                }
+            }
+        }
+    }
+
+    for c in &app_paths.collections {
+        let known = derived
+            .iter()
+            .any(|d| d.name == c.name || d.files == c.files);
+        if !known {
+            differences += 1;
+            println!(
+                "- {}: not declared in {} ({})",
+                c.name,
+                cfg_file.display(),
+                c.files.display()
+            );
+        }
+    }
+
+    if differences == 0 {
+        println!("\nConfig is in sync.");
+    } else {
+        println!("\n{} difference(s) found.", differences);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = r#"# my site
+[working]
+files = "Documents/writings" # drafts
+
+# the blog
+[[collection]]
+name = "blog"
+files = "site/content/blog"
+"#;
+
+    #[test]
+    fn add_collection_keeps_comments() {
+        let out =
+            with_collection_added(SRC, "docs", "site/content/docs", Some("site/public")).unwrap();
+        assert!(out.starts_with(SRC));
+        assert!(out.contains("name = \"docs\""));
+        assert!(out.contains("images = \"site/public\""));
+        assert!(with_collection_added(SRC, "blog", "x", None).is_err());
+
+        let cfg: config::Config = toml::from_str(&out).unwrap();
+        assert_eq!(cfg.collection.len(), 2);
+    }
+
+    #[test]
+    fn remove_collection_keeps_comments() {
+        let added = with_collection_added(SRC, "docs", "site/content/docs", None).unwrap();
+        let out = with_collection_removed(&added, "docs").unwrap();
+        assert!(out.contains("# my site"));
+        assert!(out.contains("# drafts"));
+        assert!(!out.contains("docs"));
+        assert!(with_collection_removed(SRC, "nope").is_err());
+    }
+}
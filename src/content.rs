@@ -0,0 +1,217 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// What a file holds, decided by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Markdown,
+    Yaml,
+    Json,
+    Csv,
+    /// Plain text content without front matter
+    Text,
+    Image,
+    /// Video, audio, PDFs and other downloads published along with a post
+    Asset,
+}
+
+impl Kind {
+    pub fn is_content(self) -> bool {
+        !matches!(self, Kind::Image | Kind::Asset)
+    }
+}
+
+const BUILTIN: &[(&str, Kind)] = &[
+    ("md", Kind::Markdown),
+    ("mdc", Kind::Markdown),
+    ("markdown", Kind::Markdown),
+    ("yaml", Kind::Yaml),
+    ("yml", Kind::Yaml),
+    ("json", Kind::Json),
+    ("csv", Kind::Csv),
+    ("png", Kind::Image),
+    ("jpg", Kind::Image),
+    ("jpeg", Kind::Image),
+    ("gif", Kind::Image),
+    ("webp", Kind::Image),
+    ("svg", Kind::Image),
+    ("avif", Kind::Image),
+    ("mp4", Kind::Asset),
+    ("webm", Kind::Asset),
+    ("mov", Kind::Asset),
+    ("mp3", Kind::Asset),
+    ("m4a", Kind::Asset),
+    ("ogg", Kind::Asset),
+    ("wav", Kind::Asset),
+    ("pdf", Kind::Asset),
+    ("zip", Kind::Asset),
+];
+
+/// Extra extensions from the `[types]` table of the config.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct TypesConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markdown: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub yaml: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub json: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub csv: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub text: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<String>,
+}
+
+impl TypesConfig {
+    pub fn is_empty(&self) -> bool {
+        self.markdown.is_empty()
+            && self.yaml.is_empty()
+            && self.json.is_empty()
+            && self.csv.is_empty()
+            && self.text.is_empty()
+            && self.images.is_empty()
+            && self.assets.is_empty()
+    }
+}
+
+/// Every file extension nuch knows about and what it holds.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    types: Vec<(String, Kind)>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            types: BUILTIN
+                .iter()
+                .map(|(ext, kind)| (ext.to_string(), *kind))
+                .collect(),
+        }
+    }
+}
+
+impl Registry {
+    /// Built-in types plus the ones added in the config.
+    pub fn with_config(cfg: &TypesConfig) -> Result<Self> {
+        let mut registry = Self::default();
+        let groups = [
+            (&cfg.markdown, Kind::Markdown),
+            (&cfg.yaml, Kind::Yaml),
+            (&cfg.json, Kind::Json),
+            (&cfg.csv, Kind::Csv),
+            (&cfg.text, Kind::Text),
+            (&cfg.images, Kind::Image),
+            (&cfg.assets, Kind::Asset),
+        ];
+        for (exts, kind) in groups {
+            for ext in exts {
+                registry.add(ext, kind)?;
+            }
+        }
+        Ok(registry)
+    }
+
+    fn add(&mut self, ext: &str, kind: Kind) -> Result<()> {
+        let ext = normalize(ext);
+        if ext.is_empty() {
+            return Err(anyhow::anyhow!("Empty extension in [types]"));
+        }
+        match self.types.iter().find(|(e, _)| *e == ext) {
+            Some((_, existing)) if *existing == kind => {}
+            Some((_, existing)) => {
+                return Err(anyhow::anyhow!(
+                    "Extension '{}' is already registered as {:?}",
+                    ext,
+                    existing
+                ));
+            }
+            None => self.types.push((ext, kind)),
+        }
+        Ok(())
+    }
+
+    pub fn kind_of_ext(&self, ext: &str) -> Option<Kind> {
+        let ext = normalize(ext);
+        self.types.iter().find(|(e, _)| *e == ext).map(|(_, k)| *k)
+    }
+
+    pub fn kind_of(&self, path: &Path) -> Option<Kind> {
+        path.extension()
+            .and_then(|s| s.to_str())
+            .and_then(|ext| self.kind_of_ext(ext))
+    }
+
+    pub fn is_image(&self, path: &Path) -> bool {
+        self.kind_of(path) == Some(Kind::Image)
+    }
+
+    /// Extensions of every image and asset type.
+    pub fn asset_exts(&self) -> Vec<String> {
+        self.types
+            .iter()
+            .filter(|(_, k)| !k.is_content())
+            .map(|(e, _)| e.clone())
+            .collect()
+    }
+
+    /// Extensions of every content (non-image) type.
+    pub fn content_exts(&self) -> Vec<String> {
+        self.types
+            .iter()
+            .filter(|(_, k)| k.is_content())
+            .map(|(e, _)| e.clone())
+            .collect()
+    }
+}
+
+fn normalize(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_types_are_registered() {
+        let r = Registry::default();
+        assert_eq!(r.kind_of(Path::new("post.mdc")), Some(Kind::Markdown));
+        assert_eq!(r.kind_of(Path::new("Post.MARKDOWN")), Some(Kind::Markdown));
+        assert_eq!(r.kind_of(Path::new("a.yml")), Some(Kind::Yaml));
+        assert!(r.is_image(Path::new("cover.JPG")));
+        assert!(!r.is_image(Path::new("foo.notpng")));
+        assert_eq!(r.kind_of(Path::new("notes.txt")), None);
+        assert!(r.content_exts().contains(&"mdc".to_string()));
+        assert!(!r.content_exts().contains(&"png".to_string()));
+        assert_eq!(r.kind_of(Path::new("demo.mp4")), Some(Kind::Asset));
+        assert_eq!(r.kind_of(Path::new("slides.PDF")), Some(Kind::Asset));
+        assert!(r.asset_exts().contains(&"png".to_string()));
+        assert!(!r.asset_exts().contains(&"md".to_string()));
+    }
+
+    #[test]
+    fn config_adds_types() {
+        let cfg = TypesConfig {
+            text: vec!["txt".to_string()],
+            images: vec![".AVIF".to_string()],
+            markdown: vec!["md".to_string()],
+            ..Default::default()
+        };
+        let r = Registry::with_config(&cfg).unwrap();
+        assert_eq!(r.kind_of(Path::new("notes.txt")), Some(Kind::Text));
+        assert!(r.is_image(Path::new("photo.avif")));
+        assert!(r.content_exts().contains(&"txt".to_string()));
+
+        let clash = TypesConfig {
+            images: vec!["md".to_string()],
+            ..Default::default()
+        };
+        assert!(Registry::with_config(&clash).is_err());
+    }
+}
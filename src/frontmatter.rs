@@ -0,0 +1,110 @@
+use serde_yaml_ng::{Mapping, Value};
+
+/// The `---` delimited YAML block at the top of a Markdown file.
+#[derive(Debug, PartialEq)]
+pub struct FrontMatter<'a> {
+    pub yaml: &'a str,
+    /// 1-based line number of the first YAML line in the file
+    pub first_line: usize,
+    pub body: &'a str,
+}
+
+/// Split `src` into front matter and body. Returns None when the file has no
+/// front matter or the closing `---` is missing.
+pub fn split(src: &str) -> Option<FrontMatter<'_>> {
+    let src = src.strip_prefix('\u{feff}').unwrap_or(src);
+    let first_end = src.find('\n')?;
+    if src[..first_end].trim_end() != "---" {
+        return None;
+    }
+
+    let yaml_start = first_end + 1;
+    let mut pos = yaml_start;
+    for line in src[yaml_start..].split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some(FrontMatter {
+                yaml: &src[yaml_start..pos],
+                first_line: 2,
+                body: &src[pos + line.len()..],
+            });
+        }
+        pos += line.len();
+    }
+    None
+}
+
+/// Parse front matter YAML into a mapping; empty front matter is an empty mapping.
+pub fn parse(yaml: &str) -> Result<Mapping, serde_yaml_ng::Error> {
+    if yaml.trim().is_empty() {
+        return Ok(Mapping::new());
+    }
+    serde_yaml_ng::from_str(yaml)
+}
+
+/// A scalar field rendered as a string (`date: 2024-01-02` -> "2024-01-02").
+pub fn field_str(map: &Mapping, key: &str) -> Option<String> {
+    match map.get(key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// A list field such as `tags: [a, b]`; a plain string is split on commas.
+pub fn field_list(map: &Mapping, key: &str) -> Vec<String> {
+    match map.get(key) {
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect(),
+        Some(Value::String(s)) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn word_count(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|w| w.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_finds_front_matter() {
+        let src = "---\ntitle: Hello\ndate: 2024-05-01\n---\n# Hello\n\nSome words here.\n";
+        let fm = split(src).unwrap();
+        assert_eq!(fm.yaml, "title: Hello\ndate: 2024-05-01\n");
+        assert_eq!(fm.body, "# Hello\n\nSome words here.\n");
+
+        let map = parse(fm.yaml).unwrap();
+        assert_eq!(field_str(&map, "title").as_deref(), Some("Hello"));
+        assert_eq!(field_str(&map, "date").as_deref(), Some("2024-05-01"));
+        assert_eq!(word_count(fm.body), 4);
+
+        let tags = parse("tags: [rust, cli]\nkeywords: a, b\n").unwrap();
+        assert_eq!(field_list(&tags, "tags"), ["rust", "cli"]);
+        assert_eq!(field_list(&tags, "keywords"), ["a", "b"]);
+        assert!(field_list(&tags, "missing").is_empty());
+    }
+
+    #[test]
+    fn split_rejects_missing_or_unclosed() {
+        assert!(split("# No front matter\n").is_none());
+        assert!(split("---\ntitle: x\n").is_none());
+        assert!(split("").is_none());
+        assert_eq!(split("---\n---\nbody").unwrap().yaml, "");
+    }
+}
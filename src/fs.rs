@@ -1,34 +1,176 @@
 use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-pub fn dir_has_supported_files(dir: &std::path::Path) -> Result<bool, std::io::Error> {
-    if !dir.is_dir() {
-        return Ok(false);
+use crate::content::Registry;
+
+/// Decides which files in a working or collection directory are content.
+///
+/// Patterns without a `/` match the file name at any depth (like `.gitignore`);
+/// patterns with a `/` or `**` match the path relative to the directory.
+/// Subdirectories are only scanned when an include pattern is a path pattern,
+/// e.g. `**/*.md`.
+#[derive(Debug, Clone)]
+pub struct FileFilter {
+    include: Option<(GlobSet, GlobSet)>,
+    exclude: Option<(GlobSet, GlobSet)>,
+    types: Vec<String>,
+    recursive: bool,
+}
+
+impl Default for FileFilter {
+    fn default() -> Self {
+        Self {
+            include: None,
+            exclude: None,
+            types: Registry::default().content_exts(),
+            recursive: false,
+        }
     }
-    let supported_exts = ["md", "yaml", "yml", "json", "csv"];
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_file()
-            && let Some(ext) = path.extension().and_then(|s| s.to_str())
-            && supported_exts.contains(&ext)
-        {
-            return Ok(true);
+}
+
+impl FileFilter {
+    /// `types` are extensions without the dot and must be content types known to
+    /// `registry`; None allows every content type in the registry.
+    pub fn new(
+        include: &[String],
+        exclude: &[String],
+        types: Option<&[String]>,
+        registry: &Registry,
+    ) -> Result<Self> {
+        let mut filter = Self {
+            types: registry.content_exts(),
+            ..Self::default()
+        };
+        if let Some(types) = types {
+            filter.types = Vec::new();
+            for t in types {
+                let ext = t.trim_start_matches('.').to_lowercase();
+                if !registry.kind_of_ext(&ext).is_some_and(|k| k.is_content()) {
+                    return Err(anyhow::anyhow!(
+                        "Unknown content type '{}'; add it to the [types] table first",
+                        t
+                    ));
+                }
+                filter.types.push(ext);
+            }
+        }
+        if !include.is_empty() {
+            filter.include = Some(build_globs(include)?);
+            filter.recursive = include.iter().any(|p| is_path_pattern(p));
+        }
+        if !exclude.is_empty() {
+            filter.exclude = Some(build_globs(exclude)?);
+        }
+        Ok(filter)
+    }
+
+    pub fn types(&self) -> &[String] {
+        &self.types
+    }
+
+    /// Whether `rel`, a file path relative to the filtered directory, is content.
+    pub fn matches(&self, rel: &Path) -> bool {
+        if !has_type(rel, &self.types) {
+            return false;
+        }
+        let included = self
+            .include
+            .as_ref()
+            .is_none_or(|globs| glob_match(globs, rel));
+        let excluded = self
+            .exclude
+            .as_ref()
+            .is_some_and(|globs| glob_match(globs, rel));
+        included && !excluded
+    }
+
+    /// All matching files under `dir`, sorted.
+    pub fn list(&self, dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut files = Vec::new();
+        if dir.is_dir() {
+            self.collect(dir, dir, &mut files)?;
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn collect(&self, root: &Path, dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                let hidden = path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|n| n.starts_with('.'));
+                if self.recursive && !hidden {
+                    self.collect(root, &path, out)?;
+                }
+            } else if path.is_file() && self.matches(path.strip_prefix(root).unwrap_or(&path)) {
+                out.push(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split patterns into (name patterns, path patterns) glob sets.
+fn build_globs(patterns: &[String]) -> Result<(GlobSet, GlobSet)> {
+    let mut names = GlobSetBuilder::new();
+    let mut paths = GlobSetBuilder::new();
+    for p in patterns {
+        let glob = GlobBuilder::new(p)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Invalid glob '{}': {}", p, e))?;
+        if is_path_pattern(p) {
+            paths.add(glob);
+        } else {
+            names.add(glob);
         }
     }
-    Ok(false)
+    Ok((names.build()?, paths.build()?))
 }
 
+fn is_path_pattern(p: &str) -> bool {
+    p.contains('/') || p.contains("**")
+}
+
+fn glob_match((names, paths): &(GlobSet, GlobSet), rel: &Path) -> bool {
+    let rel_str = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let name_hit = rel
+        .file_name()
+        .is_some_and(|n| names.is_match(Path::new(n)));
+    name_hit || paths.is_match(&rel_str)
+}
+
+pub fn dir_has_supported_files(dir: &Path, filter: &FileFilter) -> Result<bool, std::io::Error> {
+    Ok(!filter.list(dir)?.is_empty())
+}
+
+/// Whether `path` has one of the lowercase extensions in `types`.
+pub fn has_type(path: &Path, types: &[String]) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| types.iter().any(|t| t.eq_ignore_ascii_case(ext)))
+}
+
+/// Files in `dir` whose names start with `stem_lower` and whose extension is
+/// one of `types`.
 pub fn matching_images_for_stem(
     stem_lower: &str,
     dir: &Path,
+    types: &[String],
 ) -> Result<Vec<PathBuf>, std::io::Error> {
     let mut images = Vec::new();
     if !dir.is_dir() {
         return Ok(images);
     }
-    let exts = ["png", "jpg", "jpeg", "gif", "webp", "svg"];
     for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let p = entry.path();
@@ -40,34 +182,15 @@ pub fn matching_images_for_stem(
             if !name_lower.starts_with(stem_lower) {
                 continue;
             }
-            if exts.iter().any(|ext| name_lower.ends_with(ext)) {
+            if has_type(&p, types) {
                 images.push(p);
             }
         }
     }
+    images.sort();
     Ok(images)
 }
 
-pub fn copy_file_to(src: &PathBuf, dst_dir: &PathBuf) -> Result<PathBuf> {
-    fs::create_dir_all(dst_dir)?;
-    let dst = dst_dir.join(src.file_name().unwrap());
-    if dst.exists() {
-        return Err(anyhow::anyhow!(
-            "Destination already exists: {}",
-            dst.display()
-        ));
-    }
-    fs::copy(src, &dst).map_err(|e| {
-        anyhow::anyhow!(
-            "Failed to copy {} to {}: {}",
-            src.display(),
-            dst.display(),
-            e
-        )
-    })?;
-    Ok(dst)
-}
-
 #[allow(dead_code)]
 pub fn remove_files(paths: &[PathBuf]) -> Result<()> {
     for p in paths {
@@ -99,11 +222,13 @@ pub fn backup_files_to_temp(files: &[PathBuf]) -> Result<(PathBuf, Vec<(PathBuf,
     fs::create_dir_all(&tmp)?;
 
     let mut pairs = Vec::new();
-    for orig in files {
+    for (i, orig) in files.iter().enumerate() {
         if !orig.exists() {
             continue;
         }
-        let dest = tmp.join(orig.file_name().unwrap());
+        // numbered, so files with the same name from different dirs don't collide
+        let name = orig.file_name().unwrap().to_string_lossy();
+        let dest = tmp.join(format!("{}-{}", i, name));
         fs::copy(orig, &dest)
             .map_err(|e| anyhow::anyhow!("Failed to backup {}: {}", orig.display(), e))?;
         pairs.push((orig.clone(), dest));
@@ -115,6 +240,10 @@ pub fn backup_files_to_temp(files: &[PathBuf]) -> Result<(PathBuf, Vec<(PathBuf,
 pub fn restore_from_backups(pairs: &[(PathBuf, PathBuf)]) -> Result<()> {
     for (orig, backup) in pairs {
         if backup.exists() {
+            // the file's directory may have been removed along with it
+            if let Some(parent) = orig.parent() {
+                fs::create_dir_all(parent)?;
+            }
             fs::copy(backup, orig)
                 .map_err(|e| anyhow::anyhow!("Failed to restore {}: {}", orig.display(), e))?;
         }
@@ -122,6 +251,18 @@ pub fn restore_from_backups(pairs: &[(PathBuf, PathBuf)]) -> Result<()> {
     Ok(())
 }
 
+/// Remove `dir` and its subdirectories if no files are left in them.
+pub fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+    let _ = fs::remove_dir(dir);
+}
+
 pub fn cleanup_backup_dir(dir: &PathBuf) {
     if dir.exists() && dir.is_dir() {
         if let Ok(entries) = fs::read_dir(dir) {
@@ -142,41 +283,122 @@ mod tests {
 
     #[test]
     fn dir_has_supported_files_detects_files() {
+        let filter = FileFilter::default();
         let td = tempdir().unwrap();
-        assert!(!dir_has_supported_files(td.path()).unwrap());
+        assert!(!dir_has_supported_files(td.path(), &filter).unwrap());
 
         // Test markdown
         let md = td.path().join("post.md");
         let mut f = File::create(&md).unwrap();
         writeln!(f, "# hello").unwrap();
-        assert!(dir_has_supported_files(td.path()).unwrap());
+        assert!(dir_has_supported_files(td.path(), &filter).unwrap());
 
         // Test other formats
-        let td2 = tempdir().unwrap();
-        for ext in ["yaml", "yml", "json", "csv"] {
+        for ext in ["mdc", "markdown", "yaml", "yml", "json", "csv"] {
+            let td2 = tempdir().unwrap();
             let file = td2.path().join(format!("data.{}", ext));
             File::create(&file).unwrap();
-            assert!(dir_has_supported_files(td2.path()).unwrap());
+            assert!(dir_has_supported_files(td2.path(), &filter).unwrap());
+        }
+    }
+
+    #[test]
+    fn file_filter_applies_globs_and_types() {
+        let td = tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("nested/deeper")).unwrap();
+        std::fs::create_dir_all(td.path().join(".git")).unwrap();
+        for name in [
+            "post.md",
+            "_partial.md",
+            "idea.draft.md",
+            "README.md",
+            "data.json",
+            "nested/inner.md",
+            "nested/_skip.md",
+            "nested/deeper/deep.md",
+            ".git/HEAD.md",
+        ] {
+            std::fs::write(td.path().join(name), b"x").unwrap();
         }
+        let names = |filter: &FileFilter| -> Vec<String> {
+            filter
+                .list(td.path())
+                .unwrap()
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(td.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect()
+        };
+        let strs = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let exclude = strs(&["_*.md", "*.draft.md", "README.md"]);
+
+        // default: every supported type, top level only
+        let all = FileFilter::default();
+        assert_eq!(
+            names(&all),
+            [
+                "README.md",
+                "_partial.md",
+                "data.json",
+                "idea.draft.md",
+                "post.md"
+            ]
+        );
+
+        let registry = Registry::default();
+        let blog = FileFilter::new(&[], &exclude, Some(&strs(&["md"])), &registry).unwrap();
+        assert_eq!(names(&blog), ["post.md"]);
+
+        let data = FileFilter::new(&[], &[], Some(&strs(&[".json", "yaml"])), &registry).unwrap();
+        assert_eq!(names(&data), ["data.json"]);
+
+        let nested = FileFilter::new(&strs(&["**/*.md"]), &exclude, None, &registry).unwrap();
+        assert_eq!(
+            names(&nested),
+            ["nested/deeper/deep.md", "nested/inner.md", "post.md"]
+        );
+
+        let one_level = FileFilter::new(&strs(&["nested/*.md"]), &[], None, &registry).unwrap();
+        assert_eq!(names(&one_level), ["nested/_skip.md", "nested/inner.md"]);
+
+        assert!(FileFilter::new(&strs(&["[oops"]), &[], None, &registry).is_err());
+        assert!(FileFilter::new(&[], &[], Some(&strs(&["txt"])), &registry).is_err());
+        assert!(FileFilter::new(&[], &[], Some(&strs(&["png"])), &registry).is_err());
     }
 
     #[test]
     fn matching_images_for_stem_filters_correctly() {
         let td = tempdir().unwrap();
-        let files = ["post1.png", "post1-thumb.JPG", "other.png", "post1.txt"];
+        let files = [
+            "post1.png",
+            "post1-thumb.JPG",
+            "other.png",
+            "post1.txt",
+            "post1.notpng",
+            "post1-demo.mp4",
+        ];
         for name in files.iter() {
             let p = td.path().join(name);
             std::fs::write(&p, b"data").unwrap();
         }
 
-        let mut matches = matching_images_for_stem("post1", td.path()).unwrap();
+        let types = Registry::default().asset_exts();
+        let mut matches = matching_images_for_stem("post1", td.path(), &types).unwrap();
         matches.sort();
-        assert_eq!(matches.len(), 2);
+        assert_eq!(matches.len(), 3);
         let names: Vec<_> = matches
             .iter()
             .map(|p| p.file_name().unwrap().to_str().unwrap().to_lowercase())
             .collect();
         assert!(names.contains(&"post1.png".to_string()));
         assert!(names.contains(&"post1-thumb.jpg".to_string()));
+        assert!(names.contains(&"post1-demo.mp4".to_string()));
+
+        let png = matching_images_for_stem("post1", td.path(), &["png".to_string()]).unwrap();
+        assert_eq!(png, [td.path().join("post1.png")]);
     }
 }
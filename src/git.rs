@@ -1,7 +1,30 @@
 use anyhow::Result;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output};
+use std::sync::Mutex;
+
+/// What `git add`, `commit` and `push` printed since the last `take_output`,
+/// for the TUI's log pane.
+static OUTPUT: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn record_output(step: &str, out: &Output) {
+    let mut lines = OUTPUT.lock().unwrap_or_else(|e| e.into_inner());
+    lines.push(format!("$ git {}", step));
+    for text in [&out.stdout, &out.stderr] {
+        lines.extend(
+            String::from_utf8_lossy(text)
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| format!("  {}", l.trim_end())),
+        );
+    }
+}
+
+/// The git output recorded by `run_git_steps` since the last call.
+pub fn take_output() -> Vec<String> {
+    std::mem::take(&mut *OUTPUT.lock().unwrap_or_else(|e| e.into_inner()))
+}
 
 pub fn get_site_root(published: &Path) -> PathBuf {
     for anc in published.ancestors() {
@@ -70,6 +93,7 @@ pub fn run_git_steps(site_root: &Path, commit_msg: &str, paths: &[PathBuf]) -> R
         .args(&rels)
         .current_dir(site_root)
         .output()?;
+    record_output("add", &git_add);
     if !git_add.status.success() {
         return Err(anyhow::anyhow!(
             "git add failed: {}",
@@ -83,6 +107,7 @@ pub fn run_git_steps(site_root: &Path, commit_msg: &str, paths: &[PathBuf]) -> R
         .arg(commit_msg)
         .current_dir(site_root)
         .output()?;
+    record_output("commit", &git_commit);
     if !git_commit.status.success() {
         reset_paths(site_root, &rels);
         return Err(anyhow::anyhow!(
@@ -95,6 +120,7 @@ pub fn run_git_steps(site_root: &Path, commit_msg: &str, paths: &[PathBuf]) -> R
         .arg("push")
         .current_dir(site_root)
         .output()?;
+    record_output("push", &git_push);
     if !git_push.status.success() {
         reset_paths(site_root, &rels);
         return Err(anyhow::anyhow!(
@@ -105,3 +131,74 @@ pub fn run_git_steps(site_root: &Path, commit_msg: &str, paths: &[PathBuf]) -> R
 
     Ok(())
 }
+
+/// Every file git tracks in `site_root`; empty when it isn't a repo.
+pub fn tracked_files(site_root: &Path) -> Vec<PathBuf> {
+    let Ok(out) = Command::new("git")
+        .args(["ls-files", "-z"])
+        .current_dir(site_root)
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !out.status.success() {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(&out.stdout)
+        .split('\0')
+        .filter(|rel| !rel.is_empty())
+        .map(|rel| site_root.join(rel))
+        .collect()
+}
+
+/// Unified diff between two files, empty when they are identical.
+pub fn diff_files(old: &Path, new: &Path) -> Result<String> {
+    let out = Command::new("git")
+        .args(["diff", "--no-index", "--no-color", "--"])
+        .arg(old)
+        .arg(new)
+        .output()?;
+    // exit code 1 just means the files differ
+    match out.status.code() {
+        Some(0) | Some(1) => Ok(String::from_utf8_lossy(&out.stdout).to_string()),
+        _ => Err(anyhow::anyhow!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&out.stderr)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn output_of_every_step_is_kept_for_the_log() {
+        let td = tempdir().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(td.path())
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "nuch"]);
+        git(&["config", "user.email", "nuch@example.com"]);
+        let file = td.path().join("a.md");
+        std::fs::write(&file, "a").unwrap();
+
+        take_output();
+        // no remote, so the push fails after the commit
+        let err = run_git_steps(td.path(), "Add a.md", &[file]).unwrap_err();
+        assert!(err.to_string().starts_with("git push failed"));
+        let output = take_output();
+        let step = |s: &str| output.iter().position(|l| l == s).unwrap();
+        assert!(step("$ git add") < step("$ git commit"));
+        assert!(step("$ git commit") < step("$ git push"));
+        assert!(output.iter().any(|l| l.contains("Add a.md")));
+        assert!(output.len() > step("$ git push") + 1);
+        assert!(take_output().is_empty());
+    }
+}
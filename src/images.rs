@@ -0,0 +1,636 @@
+use anyhow::Result;
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilter, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use inquire::Confirm;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use crate::config::{AppPaths, CollectionPaths};
+use crate::fs as fs_helpers;
+use crate::git as git_helpers;
+use crate::scrub;
+
+/// `[collection.optimize]`: how images are processed on their way into a collection.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct OptimizeConfig {
+    /// Scale images down to fit these dimensions, keeping the aspect ratio
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u32>,
+    /// JPEG and AVIF quality, 1-100
+    pub quality: u8,
+    pub format: OutputFormat,
+}
+
+impl Default for OptimizeConfig {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            max_height: None,
+            quality: 80,
+            format: OutputFormat::Keep,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Re-encode in the image's own format
+    #[default]
+    Keep,
+    Jpeg,
+    /// Lossless WebP
+    Webp,
+    Avif,
+    /// Lossless WebP, or JPEG (AVIF with transparency) when WebP doesn't make
+    /// the image any smaller
+    Auto,
+}
+
+/// Formats the pipeline decodes; anything else (SVG, GIF, ...) is copied as is.
+fn is_processable(path: &Path) -> bool {
+    matches!(
+        ext_lower(path).as_deref(),
+        Some("jpg" | "jpeg" | "png" | "webp")
+    )
+}
+
+fn ext_lower(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+}
+
+/// The name and bytes to publish `src` under. With `opts`, JPEG, PNG and WebP
+/// images are resized and re-encoded, which also drops EXIF and other metadata;
+/// only a format change renames them. Otherwise the file as it is, minus its
+/// metadata when `scrub` is set.
+pub fn published_image(
+    src: &Path,
+    opts: Option<&OptimizeConfig>,
+    scrub: bool,
+) -> Result<(String, Vec<u8>)> {
+    let name = src
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let original =
+        fs::read(src).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", src.display(), e))?;
+    let scrub_failed =
+        |e| anyhow::anyhow!("Failed to strip metadata from {}: {}", src.display(), e);
+    if let Some(opts) = opts
+        && is_processable(src)
+    {
+        return match optimize(&original, src, opts)
+            .map_err(|e| anyhow::anyhow!("Failed to optimize {}: {}", src.display(), e))?
+        {
+            Some(optimized) => Ok(optimized),
+            // re-encoding didn't make it smaller: the original, minus its metadata
+            None => {
+                println!(
+                    "  {} is published as it was; re-encoding didn't make it smaller",
+                    name
+                );
+                scrub::scrub(&original)
+                    .map(|bytes| (name, bytes))
+                    .map_err(scrub_failed)
+            }
+        };
+    }
+    if scrub {
+        return scrub::scrub(&original)
+            .map(|bytes| (name, bytes))
+            .map_err(scrub_failed);
+    }
+    Ok((name, original))
+}
+
+/// The re-encoded image and its name, or None when it comes out no smaller
+/// than `original`.
+fn optimize(
+    original: &[u8],
+    src: &Path,
+    opts: &OptimizeConfig,
+) -> Result<Option<(String, Vec<u8>)>> {
+    let mut decoder = ImageReader::new(Cursor::new(original))
+        .with_guessed_format()?
+        .into_decoder()?;
+    // camera photos are often stored sideways with an EXIF rotation; apply it
+    // before the EXIF block is dropped
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+
+    let max_w = opts.max_width.unwrap_or(u32::MAX);
+    let max_h = opts.max_height.unwrap_or(u32::MAX);
+    let resized = img.width() > max_w || img.height() > max_h;
+    if resized {
+        img = img.resize(max_w, max_h, FilterType::Lanczos3);
+    }
+
+    let own = match ext_lower(src).as_deref() {
+        Some("png") => "png",
+        Some("webp") => "webp",
+        _ => "jpeg",
+    };
+    let mut format = match opts.format {
+        OutputFormat::Keep => own,
+        OutputFormat::Jpeg => "jpeg",
+        OutputFormat::Webp | OutputFormat::Auto => "webp",
+        OutputFormat::Avif => "avif",
+    };
+    let quality = opts.quality.clamp(1, 100);
+    let mut out = encode(&img, format, quality)?;
+    if opts.format == OutputFormat::Auto && out.len() >= original.len() {
+        let lossy = if img.color().has_alpha() {
+            "avif"
+        } else {
+            "jpeg"
+        };
+        let smaller = encode(&img, lossy, quality)?;
+        if smaller.len() < out.len() {
+            (format, out) = (lossy, smaller);
+        }
+    }
+    if out.len() >= original.len() {
+        return Ok(None);
+    }
+
+    let name = if format == own && opts.format == OutputFormat::Keep {
+        src.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    } else {
+        let stem = src.file_stem().unwrap_or_default().to_string_lossy();
+        let ext = if format == "jpeg" { "jpg" } else { format };
+        format!("{}.{}", stem, ext)
+    };
+    Ok(Some((name, out)))
+}
+
+fn encode(img: &DynamicImage, format: &str, quality: u8) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match format {
+        "png" => img.write_with_encoder(PngEncoder::new_with_quality(
+            &mut out,
+            CompressionType::Best,
+            PngFilter::Adaptive,
+        ))?,
+        "jpeg" => DynamicImage::ImageRgb8(img.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, quality))?,
+        "webp" => eight_bit(img).write_with_encoder(WebPEncoder::new_lossless(&mut out))?,
+        _ => eight_bit(img)
+            .write_with_encoder(AvifEncoder::new_with_speed_quality(&mut out, 6, quality))?,
+    }
+    Ok(out)
+}
+
+/// RGB or RGBA with 8 bits per channel, which every encoder accepts.
+fn eight_bit(img: &DynamicImage) -> DynamicImage {
+    if img.color().has_alpha() {
+        DynamicImage::ImageRgba8(img.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    }
+}
+
+/// `6291456` -> `6.0 MB`
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Where an image ends up in its collection's images dir.
+#[derive(Debug, PartialEq)]
+pub enum Placement {
+    /// Nothing there yet under this name
+    New(String),
+    /// The same content is already published under this name
+    Existing(String),
+}
+
+/// Pick the name to publish `bytes` under in `dir`: `name` when it's free or holds
+/// the same content, otherwise `<stem>-<hash8>.<ext>`.
+pub fn place(dir: &Path, name: &str, bytes: &[u8]) -> Result<Placement> {
+    let hash = Sha256::digest(bytes);
+    for candidate in [name.to_string(), hashed_name(name, bytes)] {
+        let path = dir.join(&candidate);
+        if !path.exists() {
+            return Ok(Placement::New(candidate));
+        }
+        if Sha256::digest(fs::read(&path)?) == hash {
+            return Ok(Placement::Existing(candidate));
+        }
+    }
+    Err(anyhow::anyhow!(
+        "{} and its hashed name both exist in {} with different content",
+        name,
+        dir.display()
+    ))
+}
+
+/// `cover.png` -> `cover-1a2b3c4d.png`, from the first 8 hex digits of the SHA-256.
+pub fn hashed_name(name: &str, bytes: &[u8]) -> String {
+    let hash = Sha256::digest(bytes);
+    let short: String = hash[..4].iter().map(|b| format!("{:02x}", b)).collect();
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}-{}.{}", stem, short, ext),
+        _ => format!("{}-{}", name, short),
+    }
+}
+
+/// Byte offsets where `name` appears as a whole file name: in `/images/a.png`
+/// but not in `cta.png`.
+fn name_positions(text: &str, name: &str) -> Vec<usize> {
+    let boundary =
+        |c: Option<char>| c.is_none_or(|c| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')));
+    text.match_indices(name)
+        .map(|(i, _)| i)
+        .filter(|&i| {
+            boundary(text[..i].chars().last()) && boundary(text[i + name.len()..].chars().next())
+        })
+        .collect()
+}
+
+/// Whether `text` refers to the file `name`.
+pub fn references(text: &str, name: &str) -> bool {
+    !name_positions(text, name).is_empty()
+}
+
+/// Point references to renamed images at their new names. All renames are
+/// applied in one pass, the longest match first, so a new name is never
+/// rewritten again.
+pub fn rewrite_references(text: &str, renames: &[(String, String)]) -> String {
+    let mut hits: Vec<(usize, &str, &str)> = renames
+        .iter()
+        .filter(|(old, new)| old != new)
+        .flat_map(|(old, new)| {
+            name_positions(text, old)
+                .into_iter()
+                .map(move |i| (i, old.as_str(), new.as_str()))
+        })
+        .collect();
+    hits.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.len().cmp(&a.1.len())));
+
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (i, old, new) in hits {
+        if i < last {
+            continue;
+        }
+        out.push_str(&text[last..i]);
+        out.push_str(new);
+        last = i + old.len();
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// The images and other assets that belong to one post in an images dir.
+#[derive(Debug, Default)]
+pub struct PostImages {
+    /// `<images>/<sub>/` when the post has a directory of its own
+    pub own_dir: Option<PathBuf>,
+    pub files: Vec<PathBuf>,
+}
+
+impl PostImages {
+    /// Files with one of the extensions in `types`: everything below `dir/<sub>/`
+    /// when that directory exists, otherwise the files in `dir` whose names
+    /// start with the post's stem.
+    pub fn find(dir: &Path, sub: Option<&str>, stem_lower: &str, types: &[String]) -> Result<Self> {
+        if let Some(sub) = sub {
+            let own = dir.join(sub);
+            if own.is_dir() {
+                return Ok(Self {
+                    files: files_below(&own, &|p| fs_helpers::has_type(p, types))?,
+                    own_dir: Some(own),
+                });
+            }
+        }
+        Ok(Self {
+            own_dir: None,
+            files: fs_helpers::matching_images_for_stem(stem_lower, dir, types)?,
+        })
+    }
+
+    /// The post's files in every directory `c` publishes assets into.
+    pub fn in_collection(c: &CollectionPaths, stem: &str) -> Result<Vec<Self>> {
+        let sub = c.post_images_dir(stem);
+        c.asset_roots()
+            .into_iter()
+            .map(|(dir, types)| Self::find(dir, sub.as_deref(), &stem.to_lowercase(), &types))
+            .collect()
+    }
+
+    /// Path of `file` inside the post's directory, or just its name.
+    pub fn inner(&self, file: &Path) -> String {
+        let rel = match &self.own_dir {
+            Some(own) => file.strip_prefix(own).unwrap_or(file),
+            None => Path::new(file.file_name().unwrap_or_default()),
+        };
+        slash_path(rel)
+    }
+}
+
+/// The images and assets of the post `stem`, for a preview: those in the working
+/// images dir, or in every dir `collection` publishes to. None when there is
+/// nowhere to look.
+pub fn preview_assets(
+    app_paths: &AppPaths,
+    collection: Option<&CollectionPaths>,
+    stem: &str,
+) -> Option<Vec<PathBuf>> {
+    let found = match collection {
+        None => vec![
+            PostImages::find(
+                app_paths.working_images.as_ref()?,
+                Some(stem),
+                &stem.to_lowercase(),
+                &app_paths.registry.asset_exts(),
+            )
+            .unwrap_or_default(),
+        ],
+        Some(c) if c.asset_roots().is_empty() => return None,
+        Some(c) => PostImages::in_collection(c, stem).unwrap_or_default(),
+    };
+    Some(found.into_iter().flat_map(|p| p.files).collect())
+}
+
+/// `a/b.png` on every platform, as it appears in a URL.
+pub fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `nuch images scrub`: strip metadata from images already published, then commit.
+pub fn run_scrub(app_paths: &AppPaths, collection: Option<&str>) -> Result<()> {
+    if let Some(name) = collection
+        && !app_paths.collections.iter().any(|c| c.name == name)
+    {
+        return Err(anyhow::anyhow!("Collection '{}' not found", name));
+    }
+
+    let mut seen: Vec<&PathBuf> = Vec::new();
+    let mut checked = 0;
+    for c in &app_paths.collections {
+        if collection.is_some_and(|name| c.name != name) {
+            continue;
+        }
+        let Some(dir) = &c.images else { continue };
+        if seen.contains(&dir) {
+            continue;
+        }
+        seen.push(dir);
+
+        let mut changes: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+        for path in files_below(dir, &|p| app_paths.registry.is_image(p))? {
+            checked += 1;
+            let bytes = fs::read(&path)?;
+            match scrub::scrub(&bytes) {
+                Ok(clean) if clean != bytes => {
+                    println!(
+                        "  {} ({} of metadata)",
+                        path.display(),
+                        human_size(bytes.len().saturating_sub(clean.len()) as u64)
+                    );
+                    changes.push((path, clean));
+                }
+                Ok(_) => {}
+                Err(e) => println!("Warning: skipping {}: {}", path.display(), e),
+            }
+        }
+        if changes.is_empty() {
+            continue;
+        }
+
+        let prompt = format!(
+            "Strip metadata from {} image(s) in {} and commit?",
+            changes.len(),
+            dir.display()
+        );
+        if !Confirm::new(&prompt).with_default(true).prompt()? {
+            println!("Skipped {}.", dir.display());
+            continue;
+        }
+
+        let paths: Vec<PathBuf> = changes.iter().map(|(p, _)| p.clone()).collect();
+        let (backup_dir, backups) = fs_helpers::backup_files_to_temp(&paths)?;
+        let written = changes
+            .iter()
+            .try_for_each(|(path, clean)| fs::write(path, clean));
+        let site_root = git_helpers::get_site_root(&c.files);
+        let result = written.map_err(anyhow::Error::from).and_then(|_| {
+            git_helpers::run_git_steps(&site_root, "Strip metadata from images", &paths)
+        });
+        if let Err(e) = result {
+            fs_helpers::restore_from_backups(&backups)?;
+            fs_helpers::cleanup_backup_dir(&backup_dir);
+            return Err(e);
+        }
+        fs_helpers::cleanup_backup_dir(&backup_dir);
+        println!("Stripped metadata from {} image(s).", changes.len());
+    }
+
+    println!("{} image(s) checked.", checked);
+    Ok(())
+}
+
+/// Every file below `dir` that `keep` accepts, skipping hidden directories.
+pub fn files_below(dir: &Path, keep: &dyn Fn(&Path) -> bool) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    if !dir.is_dir() {
+        return Ok(out);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let hidden = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'));
+            if !hidden {
+                out.extend(files_below(&path, keep)?);
+            }
+        } else if keep(&path) {
+            out.push(path);
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::Registry;
+    use image::{ImageFormat, RgbImage};
+    use tempfile::tempdir;
+
+    #[test]
+    fn images_are_resized_and_converted() {
+        let td = tempdir().unwrap();
+        let src = td.path().join("photo.jpg");
+        RgbImage::from_pixel(400, 200, image::Rgb([200, 100, 50]))
+            .save(&src)
+            .unwrap();
+
+        let opts = OptimizeConfig {
+            max_width: Some(100),
+            format: OutputFormat::Webp,
+            ..Default::default()
+        };
+        let (name, bytes) = published_image(&src, Some(&opts), false).unwrap();
+        assert_eq!(name, "photo.webp");
+        let out = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((out.width(), out.height()), (100, 50));
+        assert_eq!(image::guess_format(&bytes).unwrap(), ImageFormat::WebP);
+
+        // untouched without options, and for formats the pipeline doesn't handle
+        assert_eq!(
+            published_image(&src, None, false).unwrap(),
+            ("photo.jpg".to_string(), fs::read(&src).unwrap())
+        );
+        let svg = td.path().join("logo.svg");
+        fs::write(&svg, "<svg/>").unwrap();
+        assert_eq!(
+            published_image(&svg, Some(&opts), true).unwrap(),
+            ("logo.svg".to_string(), b"<svg/>".to_vec())
+        );
+    }
+
+    #[test]
+    fn optimizing_never_grows_a_file() {
+        let td = tempdir().unwrap();
+        // a photo-like JPEG grows as lossless WebP, so it's published as it was
+        let noise = |x: u32, y: u32| (x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503)) as u8;
+        let src = td.path().join("photo.jpg");
+        let mut original = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::from_fn(256, 256, |x, y| {
+            image::Rgb([noise(x, y), noise(y, x), noise(x + 1, y)])
+        }))
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut original, 95))
+        .unwrap();
+        fs::write(&src, &original).unwrap();
+        let mut opts = OptimizeConfig {
+            format: OutputFormat::Webp,
+            ..Default::default()
+        };
+        let (name, bytes) = published_image(&src, Some(&opts), false).unwrap();
+        assert_eq!(name, "photo.jpg");
+        assert!(bytes.len() <= original.len());
+
+        // `auto` re-encodes it as JPEG instead
+        opts.format = OutputFormat::Auto;
+        let (name, bytes) = published_image(&src, Some(&opts), false).unwrap();
+        assert_eq!(name, "photo.jpg");
+        assert_eq!(image::guess_format(&bytes).unwrap(), ImageFormat::Jpeg);
+        assert!(bytes.len() < original.len());
+
+        // a PNG that only grows when recompressed is published as it is
+        let png = td.path().join("tiny.png");
+        RgbImage::from_pixel(1, 1, image::Rgb([0, 0, 0]))
+            .save(&png)
+            .unwrap();
+        let opts = OptimizeConfig::default();
+        let (name, bytes) = published_image(&png, Some(&opts), false).unwrap();
+        assert_eq!(name, "tiny.png");
+        assert!(bytes.len() <= fs::read(&png).unwrap().len());
+    }
+
+    #[test]
+    fn references_are_rewritten() {
+        let renames = vec![("a.png".to_string(), "a.webp".to_string())];
+        let src = "![x](/images/a.png) cta.png a.png.bak\ncover: a.png";
+        assert_eq!(
+            rewrite_references(src, &renames),
+            "![x](/images/a.webp) cta.png a.png.bak\ncover: a.webp"
+        );
+
+        // overlapping names are rewritten once, preferring the longer one
+        let renames = vec![
+            ("d.svg".to_string(), "/images/p/d.svg".to_string()),
+            ("./d.svg".to_string(), "/images/p/d.svg".to_string()),
+        ];
+        assert_eq!(
+            rewrite_references("![a](./d.svg) ![b](d.svg)", &renames),
+            "![a](/images/p/d.svg) ![b](/images/p/d.svg)"
+        );
+    }
+
+    #[test]
+    fn clashing_names_get_a_hash() {
+        let td = tempdir().unwrap();
+        fs::write(td.path().join("cover.png"), b"first").unwrap();
+
+        assert_eq!(
+            place(td.path(), "cover.png", b"first").unwrap(),
+            Placement::Existing("cover.png".to_string())
+        );
+        let hashed = hashed_name("cover.png", b"second");
+        assert!(hashed.starts_with("cover-") && hashed.ends_with(".png"));
+        assert_eq!(hashed.len(), "cover-12345678.png".len());
+        assert_eq!(
+            place(td.path(), "cover.png", b"second").unwrap(),
+            Placement::New(hashed.clone())
+        );
+
+        fs::write(td.path().join(&hashed), b"second").unwrap();
+        assert_eq!(
+            place(td.path(), "cover.png", b"second").unwrap(),
+            Placement::Existing(hashed)
+        );
+        assert_eq!(
+            place(td.path(), "new.png", b"x").unwrap(),
+            Placement::New("new.png".to_string())
+        );
+    }
+
+    #[test]
+    fn post_images_prefer_their_own_dir() {
+        let td = tempdir().unwrap();
+        fs::create_dir_all(td.path().join("post/diagrams")).unwrap();
+        for name in [
+            "post-cover.png",
+            "post/cover.png",
+            "post/diagrams/flow.svg",
+            "post/notes.txt",
+        ] {
+            fs::write(td.path().join(name), b"x").unwrap();
+        }
+        let types = Registry::default().asset_exts();
+
+        let own = PostImages::find(td.path(), Some("post"), "post", &types).unwrap();
+        assert_eq!(own.own_dir, Some(td.path().join("post")));
+        let inner: Vec<String> = own.files.iter().map(|f| own.inner(f)).collect();
+        assert_eq!(inner, ["cover.png", "diagrams/flow.svg"]);
+
+        let flat = PostImages::find(td.path(), None, "post", &types).unwrap();
+        assert_eq!(flat.own_dir, None);
+        assert_eq!(flat.files, [td.path().join("post-cover.png")]);
+        assert_eq!(flat.inner(&flat.files[0]), "post-cover.png");
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+use inquire::{Confirm, MultiSelect, Text};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, CollectionConfig, Config, PickerConfig, RelativeTo, WorkingConfig};
+use crate::content::TypesConfig;
+use crate::nuxt;
+
+/// Interactively build a config from a Nuxt site repo and write it to `config_path`.
+pub fn run_init(config_path: Option<PathBuf>) -> Result<()> {
+    let config_path = config_path.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Could not determine config directory on this platform. Use --config-path to choose where to write the config."
+        )
+    })?;
+
+    if config_path.exists()
+        && !Confirm::new(&format!(
+            "Config already exists at {}. Overwrite it?",
+            config_path.display()
+        ))
+        .with_default(false)
+        .prompt()?
+    {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let site_root = absolute(&Text::new("Path to your Nuxt site repo:").prompt()?)?;
+    let content_dir = site_root.join("content");
+    if !content_dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "No content directory found at {}",
+            content_dir.display()
+        ));
+    }
+
+    let candidates = detect_collections(&site_root)?;
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|c| format!("{} ({})", c.name, c.files))
+        .collect();
+    let all: Vec<usize> = (0..labels.len()).collect();
+    let picked = MultiSelect::new("Collections to publish to:", labels.clone())
+        .with_default(&all)
+        .with_vim_mode(true)
+        .prompt_skippable()?;
+    let Some(picked) = picked else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let mut collection: Vec<CollectionConfig> = candidates
+        .into_iter()
+        .zip(labels)
+        .filter(|(_, label)| picked.contains(label))
+        .map(|(c, _)| c)
+        .collect();
+    if collection.is_empty() {
+        return Err(anyhow::anyhow!("No collections selected."));
+    }
+
+    let public_images = site_root.join("public").join("images");
+    if public_images.is_dir()
+        && Confirm::new(&format!(
+            "Copy images to {} for all collections?",
+            public_images.display()
+        ))
+        .with_default(true)
+        .prompt()?
+    {
+        for c in &mut collection {
+            c.images = Some(public_images.display().to_string());
+        }
+    }
+
+    let working_files = absolute(
+        &Text::new("Directory with your drafts:")
+            .with_help_message("must already contain at least one supported file")
+            .prompt()?,
+    )?;
+    let working_images = Text::new("Directory with your draft images (optional):")
+        .prompt_skippable()?
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| absolute(&s))
+        .transpose()?;
+
+    let cfg = Config {
+        relative_to: RelativeTo::Home,
+        types: TypesConfig::default(),
+        picker: PickerConfig::default(),
+        redirects: None,
+        working: WorkingConfig {
+            files: working_files.display().to_string(),
+            images: working_images.map(|p| p.display().to_string()),
+            ..Default::default()
+        },
+        collection,
+    };
+
+    config::validate_config(&cfg, &config_path)
+        .map_err(|e| anyhow::anyhow!("Generated config is not valid, nothing written: {}", e))?;
+
+    config::write_config(&cfg, &config_path)?;
+    println!("Wrote config to {}", config_path.display());
+    Ok(())
+}
+
+/// Collections declared in `content.config.*`, or the `content` directory and
+/// its subdirectories when there is no such file.
+fn detect_collections(site_root: &Path) -> Result<Vec<CollectionConfig>> {
+    let content_dir = site_root.join("content");
+    let mut found = Vec::new();
+
+    if let Some((cfg_file, derived)) = nuxt::load_site_collections(site_root)? {
+        for c in derived {
+            if c.files.is_dir() {
+                found.push(CollectionConfig {
+                    name: c.name,
+                    files: c.files.display().to_string(),
+                    images: None,
+                    include: c.include,
+                    exclude: c.exclude,
+                    prefix: c.prefix,
+                    ..Default::default()
+                });
+            }
+        }
+        if !found.is_empty() {
+            println!(
+                "Found {} collection(s) in {}",
+                found.len(),
+                cfg_file.display()
+            );
+            return Ok(found);
+        }
+    }
+
+    found.push(CollectionConfig {
+        name: "content".to_string(),
+        files: content_dir.display().to_string(),
+        images: None,
+        ..Default::default()
+    });
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(&content_dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .collect();
+    subdirs.sort();
+    for dir in subdirs {
+        if let Some(name) = dir.file_name().and_then(|s| s.to_str()) {
+            found.push(CollectionConfig {
+                name: name.to_string(),
+                files: dir.display().to_string(),
+                images: None,
+                ..Default::default()
+            });
+        }
+    }
+    Ok(found)
+}
+
+fn absolute(input: &str) -> Result<PathBuf> {
+    let p = Path::new(input.trim());
+    if p.as_os_str().is_empty() {
+        return Err(anyhow::anyhow!("Path is empty."));
+    }
+    if p.is_absolute() {
+        Ok(p.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()?.join(p))
+    }
+}
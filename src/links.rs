@@ -0,0 +1,587 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::{AppPaths, CollectionPaths};
+use crate::content::{Kind, Registry};
+use crate::frontmatter;
+use crate::git as git_helpers;
+
+/// A link or image reference found in a published file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub source: PathBuf,
+    /// 1-based line in the source file
+    pub line: usize,
+    pub target: String,
+    pub image: bool,
+}
+
+/// Every route served by the collections and every link between their files.
+pub struct LinkIndex {
+    /// Lowercased route -> file
+    routes: BTreeMap<String, PathBuf>,
+    /// Route prefixes of the collections; links elsewhere belong to other pages
+    prefixes: Vec<String>,
+    links: Vec<Link>,
+    public_dirs: Vec<PathBuf>,
+}
+
+impl LinkIndex {
+    pub fn build(app_paths: &AppPaths) -> Result<Self> {
+        let mut index = LinkIndex {
+            routes: BTreeMap::new(),
+            prefixes: Vec::new(),
+            links: Vec::new(),
+            public_dirs: Vec::new(),
+        };
+
+        for c in &app_paths.collections {
+            let public = git_helpers::get_site_root(&c.files).join("public");
+            if !index.public_dirs.contains(&public) {
+                index.public_dirs.push(public);
+            }
+            index.prefixes.push(collection_route(c));
+
+            for file in c.filter.list(&c.files)? {
+                if app_paths.registry.kind_of(&file) != Some(Kind::Markdown) {
+                    continue;
+                }
+                index.routes.insert(route_of(c, &file), file.clone());
+                let src = fs::read_to_string(&file)?;
+                index
+                    .links
+                    .extend(extract_links(&src, &app_paths.registry).into_iter().map(
+                        |(line, target, image)| Link {
+                            source: file.clone(),
+                            line,
+                            target,
+                            image,
+                        },
+                    ));
+            }
+        }
+        Ok(index)
+    }
+
+    /// Links from other files that point at `file`.
+    pub fn inbound(&self, file: &Path) -> Vec<&Link> {
+        let Some(route) = self
+            .routes
+            .iter()
+            .find(|(_, f)| f.as_path() == file)
+            .map(|(r, _)| r)
+        else {
+            return Vec::new();
+        };
+        self.links
+            .iter()
+            .filter(|l| !l.image && l.source != file)
+            .filter(|l| self.resolve(l).as_ref() == Some(route))
+            .collect()
+    }
+
+    /// Internal links to routes that don't exist and images that can't be found.
+    pub fn dangling(&self) -> Vec<&Link> {
+        self.links
+            .iter()
+            .filter(|l| {
+                if l.image {
+                    !self.image_exists(l)
+                } else {
+                    self.resolve(l).is_some_and(|route| {
+                        !self.routes.contains_key(&route)
+                            && self.is_content_route(&route)
+                            && !self.public_file_exists(&route)
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// The lowercased route an internal link points to; None for external links.
+    fn resolve(&self, link: &Link) -> Option<String> {
+        let target = strip_suffixes(&link.target);
+        if target.is_empty() || is_external(target) {
+            return None;
+        }
+        let path = if target.starts_with('/') {
+            target.to_string()
+        } else {
+            // relative to the directory of the linking page's route
+            let from = self
+                .routes
+                .iter()
+                .find(|(_, f)| **f == link.source)
+                .map(|(r, _)| r.clone())?;
+            let dir = from.rsplit_once('/').map_or("", |(d, _)| d);
+            format!("{}/{}", dir, target)
+        };
+        Some(normalize_route(&path))
+    }
+
+    /// Whether `route` is one the collections would serve. A collection at the
+    /// site root shares `/` with the site's own pages, so there only routes in
+    /// a directory that holds content count.
+    fn is_content_route(&self, route: &str) -> bool {
+        self.prefixes.iter().any(|p| p != "/" && under(route, p))
+            || (self.prefixes.iter().any(|p| p == "/")
+                && route.rsplit_once('/').is_some_and(|(dir, _)| {
+                    !dir.is_empty() && self.routes.keys().any(|r| under(r, dir))
+                }))
+    }
+
+    fn image_exists(&self, link: &Link) -> bool {
+        let target = strip_suffixes(&link.target);
+        if is_external(target) {
+            return true;
+        }
+        match target.strip_prefix('/') {
+            Some(rel) => self.public_dirs.iter().any(|p| p.join(rel).is_file()),
+            None => link
+                .source
+                .parent()
+                .is_some_and(|dir| dir.join(target).is_file()),
+        }
+    }
+
+    fn public_file_exists(&self, route: &str) -> bool {
+        let rel = route.trim_start_matches('/');
+        !rel.is_empty() && self.public_dirs.iter().any(|p| p.join(rel).is_file())
+    }
+}
+
+/// `nuch links check`: report every dangling internal link and missing image.
+pub fn run_check(app_paths: &AppPaths) -> Result<()> {
+    let index = LinkIndex::build(app_paths)?;
+    let dangling = index.dangling();
+    for l in &dangling {
+        let what = if l.image {
+            "missing image"
+        } else {
+            "broken link"
+        };
+        println!("{}:{}: {} {}", l.source.display(), l.line, what, l.target);
+    }
+
+    if dangling.is_empty() {
+        println!(
+            "{} link(s) in {} file(s) checked, none broken.",
+            index.links.len(),
+            index.routes.len()
+        );
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{} broken link(s) or missing image(s)",
+            dangling.len()
+        ))
+    }
+}
+
+/// Directory routes are relative to: the site's `content/` dir when the
+/// collection lives in it, otherwise the collection's parent.
+fn content_root(files: &Path, site_root: &Path) -> PathBuf {
+    let content = site_root.join("content");
+    if files.starts_with(&content) {
+        content
+    } else {
+        files.parent().unwrap_or(files).to_path_buf()
+    }
+}
+
+/// The route Nuxt Content serves `path` at: `1.guide/2.setup.md` -> `/guide/setup`,
+/// `blog/index.md` -> `/blog`. Lowercased for comparison.
+fn route_for(content_root: &Path, path: &Path, is_file: bool) -> String {
+    let rel = path.strip_prefix(content_root).unwrap_or(path);
+    let mut parts: Vec<String> = rel
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    if is_file && let Some(last) = parts.last_mut() {
+        if let Some((stem, _)) = last.rsplit_once('.') {
+            *last = stem.to_string();
+        }
+        if last == "index" || last.ends_with(".index") {
+            parts.pop();
+        }
+    }
+    let parts: Vec<&str> = parts.iter().map(|p| strip_order_prefix(p)).collect();
+    normalize_route(&format!("/{}", parts.join("/")))
+}
+
+/// The route `file` in collection `c` is served at.
+pub fn route_of(c: &CollectionPaths, file: &Path) -> String {
+    route_in(c, file, true)
+}
+
+/// The route collection `c` is served under, such as `/blog`.
+pub fn collection_route(c: &CollectionPaths) -> String {
+    route_in(c, &c.files, false)
+}
+
+/// Routes follow the directory layout under the content root, unless the
+/// collection has a `prefix`: then `blog/hello.md` with prefix `/posts` is
+/// served at `/posts/hello`.
+fn route_in(c: &CollectionPaths, path: &Path, is_file: bool) -> String {
+    match &c.prefix {
+        Some(prefix) => {
+            normalize_route(&format!("{}{}", prefix, route_for(&c.files, path, is_file)))
+        }
+        None => {
+            let site_root = git_helpers::get_site_root(&c.files);
+            route_for(&content_root(&c.files, &site_root), path, is_file)
+        }
+    }
+}
+
+/// Point links written as `old` at `new`: inline `](old)` and `](old "title")`,
+/// and reference definitions `[id]: old`. Other mentions of `old` are left alone.
+pub fn retarget(src: &str, old: &str, new: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut last = 0;
+    for (i, _) in src.match_indices(old) {
+        let before = &src[..i];
+        let after = src[i + old.len()..].chars().next();
+        let opens = before.ends_with("](") || before.ends_with("](<") || before.ends_with("]: ");
+        let closes = after.is_none_or(|c| matches!(c, ')' | '>' | ' ' | '\t' | '\n' | '\r'));
+        if i >= last && opens && closes {
+            out.push_str(&src[last..i]);
+            out.push_str(new);
+            last = i + old.len();
+        }
+    }
+    out.push_str(&src[last..]);
+    out
+}
+
+/// A relative link written on the page at `old_route`, rewritten to reach the
+/// same place from `new_route`: `../guide/setup` from `/blog/a` is
+/// `../../guide/setup` from `/news/2025/a`. None when it doesn't need to change.
+pub fn rebase(target: &str, old_route: &str, new_route: &str) -> Option<String> {
+    let path = strip_suffixes(target);
+    let suffix = &target[path.len()..];
+    if path.is_empty() || path.starts_with('/') || is_external(path) {
+        return None;
+    }
+    let dir = |route: &str| route.rsplit_once('/').map_or("", |(d, _)| d).to_string();
+    let (old_dir, new_dir) = (dir(old_route), dir(new_route));
+    if old_dir == new_dir {
+        return None;
+    }
+
+    let dest = clean_route(&format!("{}/{}", old_dir, path));
+    let dest: Vec<&str> = dest.split('/').filter(|s| !s.is_empty()).collect();
+    let from: Vec<&str> = new_dir.split('/').filter(|s| !s.is_empty()).collect();
+    let common = dest.iter().zip(&from).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<&str> = vec![".."; from.len() - common];
+    if parts.is_empty() && path.starts_with("./") {
+        parts.push(".");
+    }
+    parts.extend(&dest[common..]);
+    let mut out = parts.join("/");
+    if path.ends_with('/') {
+        out.push('/');
+    }
+    Some(format!("{}{}", out, suffix))
+}
+
+/// Last segment of the route a file with this stem is served at: `1.Hello` -> `hello`.
+pub fn slug(stem: &str) -> String {
+    strip_order_prefix(stem).to_lowercase()
+}
+
+/// `2.setup` -> `setup`
+pub fn strip_order_prefix(name: &str) -> &str {
+    match name.split_once('.') {
+        Some((n, rest)) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => rest,
+        _ => name,
+    }
+}
+
+/// Resolve `.` and `..`, drop the trailing slash and lowercase.
+fn normalize_route(path: &str) -> String {
+    clean_route(path).to_lowercase()
+}
+
+/// Resolve `.` and `..` and drop the trailing slash, keeping the case.
+fn clean_route(path: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    for seg in path.split('/') {
+        match seg {
+            "" | "." => {}
+            ".." => {
+                out.pop();
+            }
+            s => out.push(s),
+        }
+    }
+    format!("/{}", out.join("/"))
+}
+
+fn under(route: &str, prefix: &str) -> bool {
+    route == prefix
+        || route
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// The path part of a link: `/guide/setup#install` -> `/guide/setup`.
+pub fn strip_suffixes(target: &str) -> &str {
+    let end = target.find(['#', '?']).unwrap_or(target.len());
+    &target[..end]
+}
+
+fn is_external(target: &str) -> bool {
+    target.starts_with("//")
+        || target
+            .split_once(':')
+            .is_some_and(|(scheme, _)| !scheme.contains('/'))
+}
+
+/// Links and images in Markdown, plus image paths in front matter, as
+/// (line, target, is image). Code blocks and inline code are skipped.
+pub fn extract_links(src: &str, registry: &Registry) -> Vec<(usize, String, bool)> {
+    let mut found = Vec::new();
+    let mut body_start = 0;
+
+    if let Some(fm) = frontmatter::split(src) {
+        for (i, line) in fm.yaml.lines().enumerate() {
+            let Some((_, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().trim_matches(['"', '\'']);
+            if (value.starts_with('/') || value.starts_with("./"))
+                && registry.is_image(Path::new(value))
+            {
+                found.push((fm.first_line + i, value.to_string(), true));
+            }
+        }
+        body_start = src.lines().count() - fm.body.lines().count();
+    }
+
+    let mut fence: Option<&str> = None;
+    for (i, line) in src.lines().enumerate().skip(body_start) {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+
+        // reference definition: [id]: /target "title"
+        if let Some(rest) = trimmed.strip_prefix('[')
+            && let Some((_, def)) = rest.split_once("]:")
+            && let Some(target) = def.split_whitespace().next()
+        {
+            let target = target.trim_matches(['<', '>']);
+            found.push((
+                i + 1,
+                target.to_string(),
+                registry.is_image(Path::new(target)),
+            ));
+            continue;
+        }
+
+        for (target, image) in inline_links(line) {
+            found.push((i + 1, target, image));
+        }
+    }
+    found
+}
+
+/// `[text](target "title")` and `![alt](target)` on one line, outside inline code.
+fn inline_links(line: &str) -> Vec<(String, bool)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = Vec::new();
+    let mut opens: Vec<usize> = Vec::new();
+    let mut in_code = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '`' => in_code = !in_code,
+            '\\' => i += 1,
+            '[' if !in_code => opens.push(i),
+            ']' if !in_code && chars.get(i + 1) == Some(&'(') => {
+                let Some(open) = opens.pop() else {
+                    i += 1;
+                    continue;
+                };
+                let start = i + 2;
+                let mut depth = 0;
+                let mut end = start;
+                while end < chars.len() {
+                    match chars[end] {
+                        '(' => depth += 1,
+                        ')' if depth == 0 => break,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    end += 1;
+                }
+                let inner: String = chars[start..end.min(chars.len())].iter().collect();
+                let target = inner
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .trim_matches(['<', '>']);
+                if !target.is_empty() {
+                    let image = open > 0 && chars[open - 1] == '!';
+                    out.push((target.to_string(), image));
+                }
+                i = end;
+            }
+            ']' if !in_code => {
+                opens.pop();
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_are_extracted() {
+        let src = "---\ntitle: Hi\ncover: /images/hi.png\n---\n\
+See [the setup](/guide/setup#install) and ![a cat](./cat.jpg \"Cat\").\n\
+`[not](/a-link)` [ext](https://example.com)\n\
+```\n[code](/nope)\n```\n\
+[ref]: /blog/other\n";
+        let links = extract_links(src, &Registry::default());
+        assert_eq!(
+            links,
+            [
+                (3, "/images/hi.png".to_string(), true),
+                (5, "/guide/setup#install".to_string(), false),
+                (5, "./cat.jpg".to_string(), true),
+                (6, "https://example.com".to_string(), false),
+                (10, "/blog/other".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn routes_follow_nuxt_content() {
+        let root = Path::new("/site/content");
+        let route = |p: &str| route_for(root, &root.join(p), true);
+        assert_eq!(route("blog/Hello.md"), "/blog/hello");
+        assert_eq!(route("1.guide/2.setup.md"), "/guide/setup");
+        assert_eq!(route("blog/index.md"), "/blog");
+        assert_eq!(route("index.md"), "/");
+        assert_eq!(normalize_route("/blog/a/../b/"), "/blog/b");
+        assert!(under("/blog/x", "/blog"));
+        assert!(!under("/blogroll", "/blog"));
+        assert!(!under("/about", "/"));
+        assert!(is_external("mailto:me@example.com"));
+        assert!(!is_external("/blog/a:b"));
+    }
+
+    #[test]
+    fn a_prefix_replaces_the_collections_directory_in_routes() {
+        let td = tempfile::tempdir().unwrap();
+        let files = td.path().join("content/blog");
+        let mut c = CollectionPaths {
+            name: "blog".to_string(),
+            files: files.clone(),
+            images: None,
+            filter: Default::default(),
+            prefix: None,
+            schema: Default::default(),
+            optimize: None,
+            scrub_metadata: false,
+            per_post_images: false,
+            colocated_assets: Default::default(),
+            asset_types: Vec::new(),
+            asset_dirs: Default::default(),
+        };
+        assert_eq!(
+            route_of(&c, &files.join("2024/Hello.md")),
+            "/blog/2024/hello"
+        );
+
+        c.prefix = Some("/posts/".to_string());
+        assert_eq!(collection_route(&c), "/posts");
+        assert_eq!(
+            route_of(&c, &files.join("2024/Hello.md")),
+            "/posts/2024/hello"
+        );
+        assert_eq!(route_of(&c, &files.join("index.md")), "/posts");
+    }
+
+    #[test]
+    fn a_root_collection_leaves_other_pages_alone() {
+        let td = tempfile::tempdir().unwrap();
+        let source = td.path().join("content/guide/setup.md");
+        let link = |target: &str| Link {
+            source: source.clone(),
+            line: 1,
+            target: target.to_string(),
+            image: false,
+        };
+        let index = LinkIndex {
+            routes: BTreeMap::from([
+                ("/guide/setup".to_string(), source.clone()),
+                ("/".to_string(), td.path().join("content/index.md")),
+            ]),
+            prefixes: vec!["/".to_string()],
+            // `/about` and `/contact` are Vue pages under `pages/`
+            links: vec![
+                link("/about"),
+                link("../contact"),
+                link("/guide/setup"),
+                link("/guide/missing"),
+                link("./typo"),
+            ],
+            public_dirs: vec![td.path().join("public")],
+        };
+        let dangling: Vec<&str> = index.dangling().iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(dangling, ["/guide/missing", "./typo"]);
+    }
+
+    #[test]
+    fn only_link_targets_are_retargeted() {
+        let src = "[a](/blog/hello) [b](/blog/hello#top \"T\") /blog/hello\n\
+[c](/blog/hello-world)\n[ref]: /blog/hello\n";
+        assert_eq!(
+            retarget(src, "/blog/hello", "/news/hi"),
+            "[a](/news/hi) [b](/blog/hello#top \"T\") /blog/hello\n\
+[c](/blog/hello-world)\n[ref]: /news/hi\n"
+        );
+        assert_eq!(
+            retarget("[b](/blog/hello#top)", "/blog/hello#top", "/news/hi#top"),
+            "[b](/news/hi#top)"
+        );
+    }
+
+    #[test]
+    fn relative_links_are_rebased() {
+        let rebase = |t| rebase(t, "/blog/a", "/news/2025/a");
+        assert_eq!(rebase("./other").as_deref(), Some("../../blog/other"));
+        assert_eq!(
+            rebase("../guide/Setup#install").as_deref(),
+            Some("../../guide/Setup#install")
+        );
+        assert_eq!(
+            super::rebase("./2025/b", "/blog/a", "/blog/2025/a").as_deref(),
+            Some("./b")
+        );
+        assert_eq!(rebase("/blog/other"), None);
+        assert_eq!(rebase("https://example.com"), None);
+        assert_eq!(rebase("#top"), None);
+        assert_eq!(super::rebase("./other", "/blog/a", "/blog/b"), None);
+    }
+}
@@ -0,0 +1,278 @@
+use anyhow::Result;
+use serde_yaml_ng::Mapping;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{AppPaths, CollectionPaths};
+use crate::content::{Kind, Registry};
+use crate::frontmatter;
+use crate::fs::FileFilter;
+use crate::schema::Schema;
+
+/// A problem that would break the site build.
+#[derive(Debug, PartialEq)]
+pub struct Issue {
+    /// 1-based line in the file, when known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn new(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Parse `src` as `kind` and check its fields against `schema`.
+pub fn check_source(src: &str, kind: Kind, schema: &Schema) -> Vec<Issue> {
+    match kind {
+        Kind::Markdown => check_markdown(src, schema),
+        Kind::Yaml => match serde_yaml_ng::from_str::<serde_yaml_ng::Value>(src) {
+            Ok(serde_yaml_ng::Value::Mapping(map)) => schema.check(&map, src, 1),
+            Ok(_) => not_a_mapping(schema),
+            Err(e) => vec![yaml_issue(&e, 1)],
+        },
+        Kind::Json => match serde_json::from_str::<serde_json::Value>(src) {
+            Ok(value @ serde_json::Value::Object(_)) => match serde_yaml_ng::to_value(value) {
+                Ok(serde_yaml_ng::Value::Mapping(map)) => schema.check(&map, src, 1),
+                _ => not_a_mapping(schema),
+            },
+            Ok(_) => not_a_mapping(schema),
+            Err(e) => vec![Issue::new(Some(e.line()), format!("invalid JSON: {}", e))],
+        },
+        Kind::Csv => check_csv(src),
+        Kind::Text | Kind::Image | Kind::Asset => Vec::new(),
+    }
+}
+
+/// Read and check a file; an unknown type has nothing to check.
+pub fn check_file(path: &Path, registry: &Registry, schema: &Schema) -> Vec<Issue> {
+    let Some(kind) = registry.kind_of(path) else {
+        return Vec::new();
+    };
+    match fs::read_to_string(path) {
+        Ok(src) => check_source(&src, kind, schema),
+        Err(e) => vec![Issue::new(None, format!("cannot read: {}", e))],
+    }
+}
+
+/// Refuse to publish `path` into `dest` while it has problems.
+pub fn ensure_valid(path: &Path, registry: &Registry, dest: &CollectionPaths) -> Result<()> {
+    let issues = check_file(path, registry, &dest.schema);
+    if issues.is_empty() {
+        return Ok(());
+    }
+    print_issues(path, &issues);
+    Err(anyhow::anyhow!(
+        "{} has {} problem(s); fix them before publishing to '{}'",
+        path.display(),
+        issues.len(),
+        dest.name
+    ))
+}
+
+/// `nuch lint [path]`: check one file or every listed file in a directory
+/// (the working dir by default). Fields are checked against the schema of
+/// `collection`, or of the collection the path lives in.
+pub fn run_lint(
+    path: Option<PathBuf>,
+    collection: Option<&str>,
+    app_paths: &AppPaths,
+) -> Result<()> {
+    let path = path.unwrap_or_else(|| app_paths.working_files.clone());
+    let path = std::path::absolute(&path).unwrap_or(path);
+
+    let target = match collection {
+        Some(name) => Some(
+            app_paths
+                .collections
+                .iter()
+                .find(|c| c.name == name)
+                .ok_or_else(|| anyhow::anyhow!("Collection '{}' not found", name))?,
+        ),
+        None => app_paths
+            .collections
+            .iter()
+            .find(|c| path.starts_with(&c.files)),
+    };
+    let no_schema = Schema::default();
+    let schema = target.map_or(&no_schema, |c| &c.schema);
+
+    let files = if path.is_dir() {
+        let filter = if path == app_paths.working_files {
+            app_paths.working_filter.clone()
+        } else if let Some(c) = app_paths.collections.iter().find(|c| c.files == path) {
+            c.filter.clone()
+        } else {
+            FileFilter::new(&[], &[], None, &app_paths.registry)?
+        };
+        filter.list(&path)?
+    } else if path.is_file() {
+        vec![path.clone()]
+    } else {
+        return Err(anyhow::anyhow!("{} does not exist", path.display()));
+    };
+
+    let mut problems = 0;
+    let mut bad_files = 0;
+    for file in &files {
+        let issues = check_file(file, &app_paths.registry, schema);
+        if !issues.is_empty() {
+            print_issues(file, &issues);
+            problems += issues.len();
+            bad_files += 1;
+        }
+    }
+
+    if problems == 0 {
+        println!("{} file(s) checked, no problems found.", files.len());
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "{} problem(s) in {} of {} file(s)",
+            problems,
+            bad_files,
+            files.len()
+        ))
+    }
+}
+
+fn print_issues(path: &Path, issues: &[Issue]) {
+    for issue in issues {
+        match issue.line {
+            Some(line) => println!("{}:{}: {}", path.display(), line, issue.message),
+            None => println!("{}: {}", path.display(), issue.message),
+        }
+    }
+}
+
+fn check_markdown(src: &str, schema: &Schema) -> Vec<Issue> {
+    let Some(fm) = frontmatter::split(src) else {
+        let opened = src
+            .strip_prefix('\u{feff}')
+            .unwrap_or(src)
+            .lines()
+            .next()
+            .is_some_and(|l| l.trim_end() == "---");
+        if opened {
+            return vec![Issue::new(Some(1), "front matter is not closed with ---")];
+        }
+        return schema.check(&Mapping::new(), "", 1);
+    };
+
+    match frontmatter::parse(fm.yaml) {
+        Ok(map) => schema.check(&map, fm.yaml, fm.first_line),
+        Err(e) => vec![yaml_issue(&e, fm.first_line)],
+    }
+}
+
+fn check_csv(src: &str) -> Vec<Issue> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(false)
+        .from_reader(src.as_bytes());
+    for record in reader.records() {
+        if let Err(e) = record {
+            let line = e.position().map(|p| p.line() as usize);
+            let message = match e.kind() {
+                csv::ErrorKind::UnequalLengths {
+                    expected_len, len, ..
+                } => format!("expected {} columns, found {}", expected_len, len),
+                _ => format!("invalid CSV: {}", e),
+            };
+            return vec![Issue::new(line, message)];
+        }
+    }
+    Vec::new()
+}
+
+/// `offset` is the file line the YAML starts on.
+fn yaml_issue(e: &serde_yaml_ng::Error, offset: usize) -> Issue {
+    let line = e.location().map(|l| l.line() + offset - 1);
+    Issue::new(
+        line,
+        format!("invalid YAML: {}", shift_lines(&e.to_string(), offset - 1)),
+    )
+}
+
+/// Add `by` to every "line N" in a parser message so it points into the file.
+fn shift_lines(msg: &str, by: usize) -> String {
+    let mut out = String::new();
+    let mut rest = msg;
+    while let Some(i) = rest.find("line ") {
+        let (head, tail) = rest.split_at(i + "line ".len());
+        out.push_str(head);
+        let digits = tail.chars().take_while(char::is_ascii_digit).count();
+        match tail[..digits].parse::<usize>() {
+            Ok(n) => out.push_str(&(n + by).to_string()),
+            Err(_) => out.push_str(&tail[..digits]),
+        }
+        rest = &tail[digits..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn not_a_mapping(schema: &Schema) -> Vec<Issue> {
+    if schema.is_empty() {
+        Vec::new()
+    } else {
+        vec![Issue::new(
+            Some(1),
+            "expected a mapping at the top level to hold the required fields",
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(fields: &[&str]) -> Schema {
+        let names: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+        let mut schema = Schema::default();
+        schema.require(&names);
+        schema
+    }
+
+    #[test]
+    fn markdown_front_matter_is_checked() {
+        let ok = "---\ntitle: Hi\ndate: 2024-01-01\n---\nBody\n";
+        assert!(check_source(ok, Kind::Markdown, &req(&["title", "date"])).is_empty());
+
+        let missing = check_source(ok, Kind::Markdown, &req(&["title", "description"]));
+        assert_eq!(missing.len(), 1);
+        assert!(missing[0].message.contains("description"));
+
+        let bad = "---\ntitle: Hi\ntags: [a, b\n---\nBody\n";
+        let issues = check_source(bad, Kind::Markdown, &req(&[]));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(4));
+        assert!(issues[0].message.contains("at line 4"));
+
+        let unclosed = check_source("---\ntitle: Hi\n", Kind::Markdown, &req(&[]));
+        assert_eq!(unclosed[0].line, Some(1));
+        assert!(check_source("# No front matter\n", Kind::Markdown, &req(&[])).is_empty());
+    }
+
+    #[test]
+    fn data_files_are_parsed() {
+        assert!(check_source("{\"a\": 1}", Kind::Json, &req(&[])).is_empty());
+        let json = check_source("{\n  \"a\": 1,\n}", Kind::Json, &req(&[]));
+        assert_eq!(json[0].line, Some(3));
+        assert_eq!(
+            check_source("{\"a\": 1}", Kind::Json, &req(&["b"])).len(),
+            1
+        );
+
+        assert!(check_source("a: 1\nb: [x]\n", Kind::Yaml, &req(&["a"])).is_empty());
+        assert_eq!(check_source("a: [1\n", Kind::Yaml, &req(&[])).len(), 1);
+
+        assert!(check_source("a,b\n1,2\n\"x,y\",3\n", Kind::Csv, &req(&[])).is_empty());
+        let csv = check_source("a,b\n1,2\n3\n", Kind::Csv, &req(&[]));
+        assert_eq!(csv, [Issue::new(Some(3), "expected 2 columns, found 1")]);
+    }
+}
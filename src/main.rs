@@ -1,11 +1,28 @@
+mod assets;
 mod config;
+mod config_cmd;
+mod content;
+mod frontmatter;
 mod fs;
 mod git;
+mod images;
+mod init;
+mod links;
+mod lint;
+mod move_cmd;
+mod nuxt;
 mod publish;
+mod redirects;
+mod refs;
+mod schedule;
+mod schema;
+mod scrub;
+mod tui;
 mod ui;
 
 use anyhow::Result;
 use clap::{CommandFactory, Parser};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
@@ -14,6 +31,14 @@ struct Args {
     #[arg(long = "config")]
     generate_config: bool,
 
+    /// Use this config file instead of the default location
+    #[arg(long = "config-path", env = "NUCH_CONFIG", global = true)]
+    config_path: Option<PathBuf>,
+
+    /// Use the named profile (`profiles/<name>.toml` in the config directory)
+    #[arg(long, env = "NUCH_PROFILE", global = true)]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -21,41 +46,260 @@ struct Args {
 #[derive(clap::Subcommand, Debug)]
 enum Command {
     /// Publish a selected Markdown file from working to publishing directory
-    Publish,
+    Publish {
+        #[command(flatten)]
+        picker: PickerArgs,
+    },
     /// Delete a selected Markdown file from publishing directory
-    Delete,
+    Delete {
+        #[command(flatten)]
+        picker: PickerArgs,
+        /// Only delete images the post itself refers to
+        #[arg(long)]
+        only_referenced: bool,
+    },
+    /// Move a published post back to the working dir, with its images
+    Unpublish {
+        #[command(flatten)]
+        picker: PickerArgs,
+        /// Only take the images the post itself refers to
+        #[arg(long)]
+        only_referenced: bool,
+    },
+    /// Move a published post to another collection or slug, with its images
+    Move {
+        /// The published file, as a path or relative to its collection
+        file: PathBuf,
+        /// Collection to move it to (defaults to its own)
+        #[arg(long)]
+        to: Option<String>,
+        /// New slug, keeping any order prefix such as `1.`
+        #[arg(long)]
+        rename: Option<String>,
+        /// Redirect the old URL to the new one (always done with a `[redirects]` table)
+        #[arg(long)]
+        redirect: bool,
+    },
+    /// Check front matter, JSON, YAML and CSV files for problems that break the site build
+    Lint {
+        /// File or directory to check (defaults to the working dir)
+        path: Option<PathBuf>,
+        /// Also check the required fields and schema of this collection
+        #[arg(long)]
+        collection: Option<String>,
+    },
+    /// Maintain published images
+    Images {
+        #[command(subcommand)]
+        command: ImagesCommand,
+    },
+    /// Check links between collection files
+    Links {
+        #[command(subcommand)]
+        command: LinksCommand,
+    },
+    /// Publish working files when their `publishAt` time comes
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommand,
+    },
+    /// Browse the working dir and collections side by side in a full-screen UI
+    Tui,
+    /// Build a config interactively from a Nuxt site repo
+    Init,
+    /// Inspect the nuch config
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum ImagesCommand {
+    /// Strip EXIF/GPS and other metadata from published images and commit the result
+    Scrub {
+        /// Only this collection's images
+        #[arg(long)]
+        collection: Option<String>,
+    },
+    /// Delete published images and assets that no file refers to, and commit the result
+    Prune {
+        /// Only this collection's images
+        #[arg(long)]
+        collection: Option<String>,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum ScheduleCommand {
+    /// Publish every working file whose publishAt has passed, without asking
+    Run {
+        /// Collection for files without publishTo
+        #[arg(long)]
+        collection: Option<String>,
+    },
+    /// Show the scheduled working files and whether they're due
+    List {
+        /// Collection for files without publishTo
+        #[arg(long)]
+        collection: Option<String>,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum LinksCommand {
+    /// Report every internal link to a missing page and every missing image
+    Check,
+}
+
+/// File picker order; each flag overrides the `[picker]` config table.
+#[derive(clap::Args, Debug)]
+struct PickerArgs {
+    /// Sort the file list
+    #[arg(long, value_enum)]
+    sort: Option<config::SortBy>,
+    /// Group the file list
+    #[arg(long, value_enum)]
+    group: Option<config::GroupBy>,
+    /// Pin the N most recently edited files at the top (0 to turn off)
+    #[arg(long, value_name = "N")]
+    pin_recent: Option<usize>,
+}
+
+impl PickerArgs {
+    fn apply(self, picker: &mut config::PickerConfig) {
+        if let Some(sort) = self.sort {
+            picker.sort = sort;
+        }
+        if let Some(group) = self.group {
+            picker.group = group;
+        }
+        if let Some(n) = self.pin_recent {
+            picker.pin_recent = n;
+        }
+    }
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum ConfigCommand {
+    /// Show the config location and resolved paths
+    Show,
+    /// Check the config and list every problem with a fix hint
+    Validate,
+    /// Open the config in $EDITOR, then validate it
+    Edit,
+    /// Add a [[collection]] entry
+    AddCollection {
+        /// Unique collection name
+        name: String,
+        /// Directory the collection's files are published to
+        files: String,
+        /// Directory the collection's images are published to
+        #[arg(long)]
+        images: Option<String>,
+    },
+    /// Remove a [[collection]] entry by name
+    RemoveCollection {
+        /// Name of the collection to remove
+        name: String,
+    },
+    /// Show differences between content.config.ts collections and the nuch config
+    Sync {
+        /// Site repo containing content.config.ts (defaults to the first collection's site)
+        #[arg(long)]
+        site: Option<PathBuf>,
+    },
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    let paths = config::load_config(args.generate_config)?;
+    let config_path = config::locate_config(args.config_path, args.profile.as_deref())?;
+    match args.command {
+        Some(Command::Init) => return init::run_init(config_path),
+        Some(Command::Config { command }) => return run_config_command(command, config_path),
+        _ => {}
+    }
+
+    let paths = config::load_config(args.generate_config, config_path)?;
     if paths.is_none() {
         return Ok(());
     }
 
-    let app_paths = paths.unwrap();
+    let mut app_paths = paths.unwrap();
 
     match args.command {
-        Some(Command::Publish) => {
-            if let Some(collection) = ui::list_collections(app_paths.collections)?
-                && let Some(selected) = ui::list_blogs(&app_paths.working_files, Some(&collection))?
+        Some(Command::Publish { picker }) => {
+            picker.apply(&mut app_paths.picker);
+            if let Some(collection) = ui::list_collections(app_paths.collections.clone())?
+                && let Some(selected) = ui::list_blogs(
+                    &app_paths.working_files,
+                    &app_paths.working_filter,
+                    Some(&collection),
+                    &app_paths,
+                )?
+            {
+                publish::publish_selected(selected, &app_paths, collection, true)?;
+            }
+        }
+        Some(Command::Delete {
+            picker,
+            only_referenced,
+        }) => {
+            picker.apply(&mut app_paths.picker);
+            if let Some(collection) = ui::list_collections(app_paths.collections.clone())?
+                && let Some(selected) =
+                    ui::list_blogs(&collection.files, &collection.filter, None, &app_paths)?
             {
-                publish::publish_selected(selected, collection, app_paths.working_images)?;
+                publish::delete_selected(selected, collection, &app_paths, only_referenced, false)?;
             }
         }
-        Some(Command::Delete) => {
-            if let Some(collection) = ui::list_collections(app_paths.collections)?
-                && let Some(selected) = ui::list_blogs(&collection.files, None)?
+        Some(Command::Unpublish {
+            picker,
+            only_referenced,
+        }) => {
+            picker.apply(&mut app_paths.picker);
+            if let Some(collection) = ui::list_collections(app_paths.collections.clone())?
+                && let Some(selected) =
+                    ui::list_blogs(&collection.files, &collection.filter, None, &app_paths)?
             {
-                publish::delete_selected(
-                    selected,
-                    collection,
-                    app_paths.working_files,
-                    app_paths.working_images,
-                )?;
+                publish::delete_selected(selected, collection, &app_paths, only_referenced, true)?;
             }
         }
+        Some(Command::Move {
+            file,
+            to,
+            rename,
+            redirect,
+        }) => move_cmd::run_move(
+            &app_paths,
+            &file,
+            to.as_deref(),
+            rename.as_deref(),
+            redirect,
+        )?,
+        Some(Command::Lint { path, collection }) => {
+            lint::run_lint(path, collection.as_deref(), &app_paths)?
+        }
+        Some(Command::Images {
+            command: ImagesCommand::Scrub { collection },
+        }) => images::run_scrub(&app_paths, collection.as_deref())?,
+        Some(Command::Images {
+            command: ImagesCommand::Prune { collection },
+        }) => refs::run_prune(&app_paths, collection.as_deref())?,
+        Some(Command::Links {
+            command: LinksCommand::Check,
+        }) => links::run_check(&app_paths)?,
+        Some(Command::Schedule {
+            command: ScheduleCommand::Run { collection },
+        }) => schedule::run_due(&app_paths, collection.as_deref())?,
+        Some(Command::Schedule {
+            command: ScheduleCommand::List { collection },
+        }) => schedule::run_list(&app_paths, collection.as_deref())?,
+        Some(Command::Tui) => tui::run_tui(&app_paths)?,
+        Some(Command::Init) | Some(Command::Config { .. }) => {
+            unreachable!("handled before loading the config")
+        }
         None => {
             println!("Error: No command provided. Use 'publish' or 'delete'.\n");
 
@@ -65,3 +309,31 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// `config` subcommands work on the raw file so they stay usable while it is invalid.
+fn run_config_command(command: ConfigCommand, config_path: Option<PathBuf>) -> Result<()> {
+    let config_path = config_path.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Could not determine config directory on this platform. Use --config-path to point at a config file."
+        )
+    })?;
+
+    match command {
+        ConfigCommand::Show => config_cmd::show(&config_path),
+        ConfigCommand::Validate => config_cmd::validate(&config_path),
+        ConfigCommand::Edit => config_cmd::edit(&config_path),
+        ConfigCommand::AddCollection {
+            name,
+            files,
+            images,
+        } => config_cmd::add_collection(&config_path, &name, &files, images.as_deref()),
+        ConfigCommand::RemoveCollection { name } => {
+            config_cmd::remove_collection(&config_path, &name)
+        }
+        ConfigCommand::Sync { site } => {
+            let cfg = config::read_config(&config_path)?;
+            let (app_paths, _) = config::check_config(&cfg, &config_path);
+            config_cmd::sync(&cfg, &app_paths, site)
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use anyhow::Result;
+use inquire::Confirm;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::assets;
+use crate::config::{AppPaths, CollectionPaths};
+use crate::fs as fs_helpers;
+use crate::git as git_helpers;
+use crate::images::{self, PostImages};
+use crate::links::{self, LinkIndex};
+use crate::publish;
+use crate::redirects::RedirectsConfig;
+use crate::refs::ImageRefs;
+
+/// What a move does to the site: files renamed, files other posts still use
+/// copied instead, and files rewritten, all committed together.
+#[derive(Default)]
+struct Plan {
+    /// Renamed on disk and staged on both paths, so git sees a rename like `git mv`
+    moves: Vec<(PathBuf, PathBuf)>,
+    copies: Vec<(PathBuf, PathBuf)>,
+    /// (file before the move, file after it, new text)
+    edits: Vec<(PathBuf, PathBuf, String)>,
+}
+
+/// `nuch move <file> --to <collection> --rename <slug>`: move a published post,
+/// its images and co-located files to another collection or slug, fix the links
+/// to it and commit.
+pub fn run_move(
+    app_paths: &AppPaths,
+    file: &Path,
+    to: Option<&str>,
+    rename: Option<&str>,
+    redirect: bool,
+) -> Result<()> {
+    if to.is_none() && rename.is_none() {
+        return Err(anyhow::anyhow!(
+            "Nothing to do: pass --to <collection>, --rename <slug> or both"
+        ));
+    }
+    let (selected, from) = find_published(app_paths, file)?;
+    let target = match to {
+        Some(name) => app_paths
+            .collections
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Collection '{}' not found", name))?,
+        None => from,
+    };
+    let site_root = git_helpers::get_site_root(&from.files);
+    if git_helpers::get_site_root(&target.files) != site_root {
+        return Err(anyhow::anyhow!(
+            "'{}' and '{}' are in different site repos; publish the post there instead",
+            from.name,
+            target.name
+        ));
+    }
+
+    let stem = publish::post_stem(&selected)?;
+    let is_index = selected.file_stem().and_then(|s| s.to_str()) != Some(stem);
+    let new_stem = match rename {
+        Some(slug) => format!(
+            "{}{}",
+            &stem[..stem.len() - links::strip_order_prefix(stem).len()],
+            slug
+        ),
+        None => stem.to_string(),
+    };
+
+    // The post is the file itself, or the whole folder of an `index` file
+    let unit = if is_index {
+        selected.parent().unwrap()
+    } else {
+        selected.as_path()
+    };
+    let rel = unit
+        .strip_prefix(&from.files)
+        .ok()
+        .filter(|r| !r.as_os_str().is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Can't move the index of collection '{}'", from.name))?;
+    let new_name = match selected.extension().and_then(|e| e.to_str()) {
+        Some(ext) if !is_index => format!("{}.{}", new_stem, ext),
+        _ => new_stem.clone(),
+    };
+    let new_unit = target
+        .files
+        .join(rel.parent().unwrap_or(Path::new("")))
+        .join(new_name);
+    if new_unit == unit {
+        return Err(anyhow::anyhow!("{} is already there", selected.display()));
+    }
+    if new_unit.exists() {
+        return Err(anyhow::anyhow!("{} already exists", new_unit.display()));
+    }
+    let new_post = if is_index {
+        new_unit.join(selected.file_name().unwrap())
+    } else {
+        new_unit.clone()
+    };
+
+    let refs = ImageRefs::build(app_paths)?;
+    let text = fs::read_to_string(&selected)?;
+    let mut plan = Plan::default();
+    plan.moves.push((unit.to_path_buf(), new_unit.clone()));
+    let mut renames: Vec<(String, String)> = Vec::new();
+    let mut own_dirs: Vec<PathBuf> = Vec::new();
+
+    // Files linked by a relative path; an index file's folder already takes them along
+    let post_dir = selected.parent().unwrap();
+    if !is_index {
+        for (_, file) in assets::colocated(&selected, &text, &app_paths.registry) {
+            let Ok(rel) = file.strip_prefix(post_dir) else {
+                continue;
+            };
+            let new_rel = match rel.strip_prefix(stem) {
+                Ok(rest) => Path::new(&new_stem).join(rest),
+                Err(_) => rel.to_path_buf(),
+            };
+            let dest = new_post.parent().unwrap().join(&new_rel);
+            if dest == file || plan.moves.iter().any(|(src, _)| *src == file) {
+                continue;
+            }
+            renames.push((images::slash_path(rel), images::slash_path(&new_rel)));
+            if let Some(parent) = file.parent().filter(|p| *p != post_dir) {
+                own_dirs.push(parent.to_path_buf());
+            }
+            plan_file(&mut plan, &refs, post_dir, &file, dest, &selected)?;
+        }
+    }
+
+    // Images named after the post or in its own folder, and anything else the
+    // post links to in the collection's images and asset dirs
+    let found = PostImages::in_collection(from, stem)?;
+    let sub = target.post_images_dir(&new_stem);
+    for ((root, _), found) in from.asset_roots().into_iter().zip(&found) {
+        let mut files: Vec<(PathBuf, String)> = found
+            .files
+            .iter()
+            .map(|f| {
+                let inner = match &found.own_dir {
+                    Some(_) => found.inner(f),
+                    None => renamed_image(&found.inner(f), stem, &new_stem),
+                };
+                (f.clone(), inner)
+            })
+            .collect();
+        if let Ok(url) = assets::public_url(root, &from.files) {
+            for (_, f) in assets::linked_images(&text, root, &url, &app_paths.registry) {
+                if !files.iter().any(|(g, _)| *g == f) {
+                    let name = f.file_name().unwrap().to_string_lossy().to_string();
+                    files.push((f, name));
+                }
+            }
+        }
+        own_dirs.extend(found.own_dir.clone());
+
+        for (file, inner) in files {
+            let Some(dest_root) = target.asset_dir(&file) else {
+                println!(
+                    "Warning: '{}' has no dir for {}; leaving it where it is",
+                    target.name,
+                    file.display()
+                );
+                continue;
+            };
+            let dest = dest_root.join(sub.as_deref().unwrap_or("")).join(&inner);
+            if dest == file || plan.moves.iter().any(|(src, _)| *src == file) {
+                continue;
+            }
+            let old_rel = images::slash_path(file.strip_prefix(root).unwrap_or(&file));
+            let new_rel = images::slash_path(dest.strip_prefix(dest_root).unwrap_or(&dest));
+            if let (Ok(old_url), Ok(new_url)) = (
+                assets::public_url(root, &from.files),
+                assets::public_url(dest_root, &target.files),
+            ) {
+                renames.push((
+                    format!("{}/{}", old_url, old_rel),
+                    format!("{}/{}", new_url, new_rel),
+                ));
+            }
+            renames.push((old_rel, new_rel));
+            plan_file(&mut plan, &refs, root, &file, dest, &selected)?;
+        }
+    }
+    // Its own relative links to other pages still reach them from the new route
+    let old_route = links::route_of(from, &selected);
+    let new_route = links::route_of(target, &new_post);
+    let mut moved = images::rewrite_references(&text, &renames);
+    let mut rebased: Vec<String> = Vec::new();
+    for (_, target, image) in links::extract_links(&text, &app_paths.registry) {
+        let is_asset = app_paths
+            .registry
+            .kind_of(Path::new(links::strip_suffixes(&target)))
+            .is_some_and(|k| !k.is_content());
+        if image || is_asset || rebased.contains(&target) {
+            continue;
+        }
+        if let Some(new) = links::rebase(&target, &old_route, &new_route) {
+            moved = links::retarget(&moved, &target, &new);
+            rebased.push(target);
+        }
+    }
+    plan.edits.push((selected.clone(), new_post.clone(), moved));
+
+    // Links from other posts follow it to its new route
+    let index = LinkIndex::build(app_paths)?;
+    let mut inbound = index.inbound(&selected);
+    inbound.retain(|l| !l.source.starts_with(unit));
+    let mut sources: Vec<&PathBuf> = inbound.iter().map(|l| &l.source).collect();
+    sources.sort();
+    sources.dedup();
+    for source in sources {
+        let mut src = fs::read_to_string(source)?;
+        for l in inbound.iter().filter(|l| l.source == *source) {
+            let suffix = &l.target[l.target.find(['#', '?']).unwrap_or(l.target.len())..];
+            src = links::retarget(&src, &l.target, &format!("{}{}", new_route, suffix));
+        }
+        plan.edits.push((source.clone(), source.clone(), src));
+    }
+    // `[redirects]` makes every move leave a redirect; `--redirect` uses the default file
+    let redirects = app_paths
+        .redirects
+        .clone()
+        .or_else(|| redirect.then(RedirectsConfig::default))
+        .filter(|_| old_route != new_route);
+
+    println!("About to move:");
+    for (src, dest) in &plan.moves {
+        println!("  {} -> {}", src.display(), dest.display());
+    }
+    for (src, dest) in &plan.copies {
+        println!(
+            "  {} -> {} (copied; still used elsewhere)",
+            src.display(),
+            dest.display()
+        );
+    }
+    if plan.edits.len() > 1 {
+        println!("Updating links in:");
+        for (file, _, _) in &plan.edits[1..] {
+            println!("  {}", file.display());
+        }
+    }
+    if redirects.is_some() {
+        println!("Redirecting {} -> {}", old_route, new_route);
+    }
+    if !Confirm::new("Proceed with move and git steps?")
+        .with_default(true)
+        .prompt()?
+    {
+        println!("Aborted by user; nothing moved.");
+        return Ok(());
+    }
+
+    let mut originals: Vec<PathBuf> = plan.edits.iter().map(|(f, _, _)| f.clone()).collect();
+    let redirects_file = redirects.as_ref().map(|r| r.path(&site_root));
+    originals.extend(redirects_file.clone());
+    let had_redirects = redirects_file.as_ref().is_some_and(|f| f.exists());
+    let (backup_dir, backups) = fs_helpers::backup_files_to_temp(&originals)?;
+
+    let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut created: Vec<PathBuf> = Vec::new();
+    let result = apply(&plan, &mut done, &mut created).and_then(|mut paths| {
+        if let Some(redirects) = &redirects {
+            paths.push(redirects.add(&site_root, &old_route, &new_route)?);
+        }
+        git_helpers::run_git_steps(
+            &site_root,
+            &format!("Move {} to {}", old_route, new_route),
+            &paths,
+        )
+    });
+    if let Err(e) = result {
+        for (src, dest) in done.iter().rev() {
+            let _ = fs::rename(dest, src);
+        }
+        let failures = fs_helpers::rollback_remove_files(&created);
+        if let Some(file) = redirects_file.filter(|_| !had_redirects) {
+            let _ = fs::remove_file(file);
+        }
+        fs_helpers::restore_from_backups(&backups)?;
+        fs_helpers::cleanup_backup_dir(&backup_dir);
+        if !failures.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{}; rollback failures: {}",
+                e,
+                failures.join("; ")
+            ));
+        }
+        return Err(e);
+    }
+    fs_helpers::cleanup_backup_dir(&backup_dir);
+    for dir in &own_dirs {
+        fs_helpers::remove_empty_dirs(dir);
+    }
+    println!("Moved {} to {}", old_route, new_route);
+    Ok(())
+}
+
+/// The published file `file` names: a path to one of the collections' files,
+/// either as given or relative to a collection.
+fn find_published<'a>(
+    app_paths: &'a AppPaths,
+    file: &Path,
+) -> Result<(PathBuf, &'a CollectionPaths)> {
+    let given = std::path::absolute(file)?;
+    for c in &app_paths.collections {
+        for candidate in [given.clone(), c.files.join(file)] {
+            if candidate.starts_with(&c.files) && candidate.is_file() {
+                return Ok((candidate, c));
+            }
+        }
+    }
+    Err(anyhow::anyhow!(
+        "{} is not a file in any collection",
+        file.display()
+    ))
+}
+
+/// Move `file` to `dest`, or copy it when files other than `post` still use it.
+fn plan_file(
+    plan: &mut Plan,
+    refs: &ImageRefs,
+    dir: &Path,
+    file: &Path,
+    dest: PathBuf,
+    post: &Path,
+) -> Result<()> {
+    if dest.exists() {
+        return Err(anyhow::anyhow!("{} already exists", dest.display()));
+    }
+    if refs.keep_reason(dir, file, post, false).is_some() {
+        plan.copies.push((file.to_path_buf(), dest));
+    } else {
+        plan.moves.push((file.to_path_buf(), dest));
+    }
+    Ok(())
+}
+
+/// An image named after the post gets the post's new name: `hello-cover.png` ->
+/// `hi-cover.png`. Others keep theirs.
+fn renamed_image(name: &str, stem: &str, new_stem: &str) -> String {
+    match name.get(..stem.len()) {
+        Some(prefix) if prefix == stem => format!("{}{}", new_stem, &name[stem.len()..]),
+        Some(prefix) if prefix.to_lowercase() == stem.to_lowercase() => {
+            format!("{}{}", new_stem.to_lowercase(), &name[stem.len()..])
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// Carry out `plan`, recording what was done so it can be undone. Returns every
+/// path git needs to stage.
+fn apply(
+    plan: &Plan,
+    done: &mut Vec<(PathBuf, PathBuf)>,
+    created: &mut Vec<PathBuf>,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for (src, dest) in &plan.moves {
+        fs::create_dir_all(dest.parent().unwrap())?;
+        fs::rename(src, dest)
+            .map_err(|e| anyhow::anyhow!("Failed to move {}: {}", src.display(), e))?;
+        done.push((src.clone(), dest.clone()));
+        paths.extend([src.clone(), dest.clone()]);
+    }
+    for (src, dest) in &plan.copies {
+        fs::create_dir_all(dest.parent().unwrap())?;
+        fs::copy(src, dest)
+            .map_err(|e| anyhow::anyhow!("Failed to copy {}: {}", src.display(), e))?;
+        created.push(dest.clone());
+        paths.push(dest.clone());
+    }
+    for (_, file, text) in &plan.edits {
+        fs::write(file, text)?;
+        if !paths.contains(file) {
+            paths.push(file.clone());
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_named_after_the_post_are_renamed() {
+        assert_eq!(
+            renamed_image("hello-cover.png", "Hello", "hi"),
+            "hi-cover.png"
+        );
+        assert_eq!(renamed_image("Hello.png", "Hello", "Hi"), "Hi.png");
+        assert_eq!(renamed_image("1.hello.png", "1.Hello", "1.Hi"), "1.hi.png");
+        assert_eq!(renamed_image("logo.svg", "Hello", "hi"), "logo.svg");
+    }
+}
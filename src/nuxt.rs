@@ -0,0 +1,503 @@
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A collection declared with `defineCollection` in a Nuxt Content v3 config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NuxtCollection {
+    pub name: String,
+    pub kind: Option<String>,
+    pub sources: Vec<NuxtSource>,
+}
+
+/// One `source` entry: either a plain glob string or `{ include, exclude, prefix }`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NuxtSource {
+    pub include: String,
+    pub exclude: Vec<String>,
+    pub prefix: Option<String>,
+}
+
+/// A Nuxt collection mapped onto the directory layout nuch works with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedCollection {
+    pub name: String,
+    /// Directory holding the collection's files (static prefix of the source glob).
+    pub files: PathBuf,
+    /// Source globs, relative to `files`.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Route the files are served under, when the source sets one
+    pub prefix: Option<String>,
+}
+
+pub fn find_content_config(site_root: &Path) -> Option<PathBuf> {
+    [
+        "content.config.ts",
+        "content.config.js",
+        "content.config.mjs",
+    ]
+    .iter()
+    .map(|name| site_root.join(name))
+    .find(|p| p.is_file())
+}
+
+/// Read `content.config.*` under `site_root` and derive one collection per source.
+/// Returns Ok(None) when the site has no content config.
+pub fn load_site_collections(
+    site_root: &Path,
+) -> Result<Option<(PathBuf, Vec<DerivedCollection>)>> {
+    let Some(cfg_file) = find_content_config(site_root) else {
+        return Ok(None);
+    };
+    let src = fs::read_to_string(&cfg_file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", cfg_file.display(), e))?;
+    let derived = derive_collections(&site_root.join("content"), &parse_collections(&src));
+    Ok(Some((cfg_file, derived)))
+}
+
+/// Map parsed collections onto directories under `content_dir`. A collection with
+/// several sources in different directories yields one entry per directory,
+/// named `<name>-<n>` after the first.
+pub fn derive_collections(content_dir: &Path, cols: &[NuxtCollection]) -> Vec<DerivedCollection> {
+    let mut out: Vec<DerivedCollection> = Vec::new();
+    for col in cols {
+        let mut mine: Vec<DerivedCollection> = Vec::new();
+        for src in &col.sources {
+            let dir = source_dir(&src.include);
+            let rel_glob = src
+                .include
+                .strip_prefix(&format!("{}/", dir.to_string_lossy()))
+                .unwrap_or(&src.include)
+                .to_string();
+            let exclude: Vec<String> = src
+                .exclude
+                .iter()
+                .map(|e| {
+                    e.strip_prefix(&format!("{}/", dir.to_string_lossy()))
+                        .unwrap_or(e)
+                        .to_string()
+                })
+                .collect();
+            let files = content_dir.join(&dir);
+            if let Some(existing) = mine.iter_mut().find(|d| d.files == files) {
+                existing.include.push(rel_glob);
+                existing.exclude.extend(exclude);
+                if existing.prefix.is_none() {
+                    existing.prefix = src.prefix.clone();
+                }
+            } else {
+                let name = if mine.is_empty() {
+                    col.name.clone()
+                } else {
+                    format!("{}-{}", col.name, mine.len() + 1)
+                };
+                mine.push(DerivedCollection {
+                    name,
+                    files,
+                    include: vec![rel_glob],
+                    exclude,
+                    prefix: src.prefix.clone(),
+                });
+            }
+        }
+        out.extend(mine);
+    }
+    out
+}
+
+/// Find `defineCollection({ ... })` calls in a content config, keyed by the
+/// object key (`blog: defineCollection(...)`) or variable (`const blog = ...`).
+pub fn parse_collections(src: &str) -> Vec<NuxtCollection> {
+    let src = strip_comments(src);
+    let mut collections = Vec::new();
+    let needle = "defineCollection(";
+    let mut search_from = 0;
+
+    while let Some(found) = src[search_from..].find(needle) {
+        let start = search_from + found;
+        search_from = start + needle.len();
+
+        let Some(name) = key_before(&src[..start]) else {
+            continue;
+        };
+        let mut parser = Parser::new(&src[search_from..]);
+        let JsValue::Object(fields) = parser.value() else {
+            continue;
+        };
+
+        let kind = fields
+            .iter()
+            .find(|(k, _)| k == "type")
+            .and_then(|(_, v)| match v {
+                JsValue::Str(s) => Some(s.clone()),
+                _ => None,
+            });
+        let sources = fields
+            .iter()
+            .find(|(k, _)| k == "source")
+            .map(|(_, v)| sources_from(v))
+            .unwrap_or_default();
+
+        collections.push(NuxtCollection {
+            name,
+            kind,
+            sources,
+        });
+    }
+
+    collections
+}
+
+/// Directory part of a source glob, e.g. `blog/**/*.md` -> `blog`.
+pub fn source_dir(source: &str) -> PathBuf {
+    let mut dir = PathBuf::new();
+    let parts: Vec<&str> = source.split('/').collect();
+    for (i, part) in parts.iter().enumerate() {
+        // the last segment is a file name or pattern, never a directory
+        if part.is_empty() || i == parts.len() - 1 || part.contains(['*', '?', '[', '{']) {
+            break;
+        }
+        dir.push(part);
+    }
+    dir
+}
+
+fn sources_from(v: &JsValue) -> Vec<NuxtSource> {
+    match v {
+        JsValue::Str(s) => vec![NuxtSource {
+            include: s.clone(),
+            ..Default::default()
+        }],
+        JsValue::Array(items) => items.iter().flat_map(sources_from).collect(),
+        JsValue::Object(fields) => {
+            let get = |key: &str| fields.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+            let Some(JsValue::Str(include)) = get("include") else {
+                return Vec::new();
+            };
+            let exclude = match get("exclude") {
+                Some(JsValue::Str(s)) => vec![s.clone()],
+                Some(JsValue::Array(items)) => items
+                    .iter()
+                    .filter_map(|i| match i {
+                        JsValue::Str(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let prefix = match get("prefix") {
+                Some(JsValue::Str(s)) => Some(s.clone()),
+                _ => None,
+            };
+            vec![NuxtSource {
+                include: include.clone(),
+                exclude,
+                prefix,
+            }]
+        }
+        JsValue::Other => Vec::new(),
+    }
+}
+
+/// Identifier (or quoted key) before `: defineCollection(` or `= defineCollection(`.
+fn key_before(before: &str) -> Option<String> {
+    let trimmed = before.trim_end();
+    let trimmed = trimmed
+        .strip_suffix(':')
+        .or_else(|| trimmed.strip_suffix('='))?
+        .trim_end();
+    let key: String = trimmed
+        .chars()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '-' | '\'' | '"'))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    let key = key.trim_matches(['\'', '"']);
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+/// Remove `//` and `/* */` comments, leaving string literals untouched.
+fn strip_comments(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == '\\' {
+                if let Some(n) = chars.next() {
+                    out.push(n);
+                }
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' | '`' => {
+                quote = Some(c);
+                out.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for n in chars.by_ref() {
+                    if n == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for n in chars.by_ref() {
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// The subset of JavaScript literals we care about; anything else is `Other`.
+#[derive(Debug, Clone, PartialEq)]
+enum JsValue {
+    Str(String),
+    Array(Vec<JsValue>),
+    Object(Vec<(String, JsValue)>),
+    Other,
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src: src.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> JsValue {
+        self.skip_ws();
+        match self.peek() {
+            Some(q @ (b'\'' | b'"' | b'`')) => match self.string(q) {
+                Some(s) => JsValue::Str(s),
+                None => JsValue::Other,
+            },
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(b']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(b',') => self.pos += 1,
+                        None => break,
+                        _ => items.push(self.value()),
+                    }
+                }
+                JsValue::Array(items)
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                loop {
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(b'}') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(b',') => self.pos += 1,
+                        None => break,
+                        _ => {
+                            let Some(key) = self.key() else {
+                                // spread or something unexpected: skip the entry
+                                self.skip_expr_or_char();
+                                continue;
+                            };
+                            self.skip_ws();
+                            if self.peek() == Some(b':') {
+                                self.pos += 1;
+                                let v = self.value();
+                                fields.push((key, v));
+                            }
+                        }
+                    }
+                }
+                JsValue::Object(fields)
+            }
+            _ => {
+                self.skip_expr_or_char();
+                JsValue::Other
+            }
+        }
+    }
+
+    fn string(&mut self, quote: u8) -> Option<String> {
+        self.pos += 1;
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == b'\\' {
+                self.pos += 2;
+                continue;
+            }
+            if c == quote {
+                let s = String::from_utf8_lossy(&self.src[start..self.pos]).to_string();
+                self.pos += 1;
+                return Some(s);
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    fn key(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(q @ (b'\'' | b'"')) => self.string(q),
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' || c == b'$' => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'$')
+                {
+                    self.pos += 1;
+                }
+                Some(String::from_utf8_lossy(&self.src[start..self.pos]).to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Like `skip_expr`, but always makes progress so callers can't loop forever.
+    fn skip_expr_or_char(&mut self) {
+        let before = self.pos;
+        self.skip_expr();
+        if self.pos == before && self.pos < self.src.len() {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip an arbitrary expression up to the next `,` or closing bracket at depth 0.
+    fn skip_expr(&mut self) {
+        let mut depth = 0usize;
+        while let Some(c) = self.peek() {
+            match c {
+                b'\'' | b'"' | b'`' => {
+                    self.string(c);
+                    continue;
+                }
+                b'(' | b'[' | b'{' => depth += 1,
+                b')' | b']' | b'}' => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                b',' if depth == 0 => return,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+import { defineContentConfig, defineCollection, z } from '@nuxt/content'
+
+// const old = defineCollection({ type: 'page', source: 'old/*.md' })
+const authors = defineCollection({ type: "data", source: "authors/*.json" })
+
+export default defineContentConfig({
+  collections: {
+    blog: defineCollection({
+      type: 'page',
+      source: {
+        include: 'blog/**/*.md',
+        exclude: ['blog/drafts/**'],
+        prefix: '/posts',
+      },
+      schema: z.object({ date: z.date(), tags: z.array(z.string()) }),
+    }),
+    /* docs are split across languages */
+    docs: defineCollection({ type: 'page', source: ['docs/en/*.md', 'docs/fa/*.md'] }),
+    authors,
+  },
+})
+"#;
+
+    #[test]
+    fn parse_collections_reads_type_and_sources() {
+        let cols = parse_collections(CONFIG);
+        let names: Vec<_> = cols.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["authors", "blog", "docs"]);
+
+        assert_eq!(cols[0].kind.as_deref(), Some("data"));
+        assert_eq!(cols[0].sources[0].include, "authors/*.json");
+
+        let blog = &cols[1];
+        assert_eq!(blog.kind.as_deref(), Some("page"));
+        assert_eq!(
+            blog.sources,
+            vec![NuxtSource {
+                include: "blog/**/*.md".to_string(),
+                exclude: vec!["blog/drafts/**".to_string()],
+                prefix: Some("/posts".to_string()),
+            }]
+        );
+        assert_eq!(cols[2].sources.len(), 2);
+    }
+
+    #[test]
+    fn derive_collections_maps_sources_to_dirs() {
+        let derived = derive_collections(Path::new("/site/content"), &parse_collections(CONFIG));
+        assert_eq!(derived.len(), 4);
+
+        let blog = derived.iter().find(|d| d.name == "blog").unwrap();
+        assert_eq!(blog.files, PathBuf::from("/site/content/blog"));
+        assert_eq!(blog.include, vec!["**/*.md".to_string()]);
+        assert_eq!(blog.exclude, vec!["drafts/**".to_string()]);
+        assert_eq!(blog.prefix.as_deref(), Some("/posts"));
+
+        let docs2 = derived.iter().find(|d| d.name == "docs-2").unwrap();
+        assert_eq!(docs2.files, PathBuf::from("/site/content/docs/fa"));
+        assert_eq!(docs2.include, vec!["*.md".to_string()]);
+    }
+
+    #[test]
+    fn source_dir_strips_globs() {
+        assert_eq!(source_dir("blog/**/*.md"), PathBuf::from("blog"));
+        assert_eq!(source_dir("docs/en/*.md"), PathBuf::from("docs/en"));
+        assert_eq!(source_dir("**"), PathBuf::new());
+        assert_eq!(source_dir("about.md"), PathBuf::new());
+    }
+}
@@ -1,24 +1,41 @@
 use anyhow::Result;
-use inquire::Confirm;
+use inquire::{Confirm, Select, Text};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::config::CollectionPaths;
+use crate::assets::{self, ColocatedAssets};
+use crate::config::{AppPaths, CollectionPaths};
+use crate::content::Registry;
 use crate::fs as fs_helpers;
 use crate::git as git_helpers;
+use crate::images::{self, Placement, PostImages};
+use crate::links::{self, LinkIndex};
+use crate::lint;
+use crate::refs::ImageRefs;
 
+/// Source, destination and the bytes to publish there.
+type FileCopy = (PathBuf, PathBuf, Vec<u8>);
+
+/// Copy a working file, its images and co-located files into a collection and
+/// commit. Without `confirm` nothing is asked, for scheduled runs.
 pub fn publish_selected(
     selected: PathBuf,
+    app_paths: &AppPaths,
     dest_path: CollectionPaths,
-    working_images: Option<PathBuf>,
+    confirm: bool,
 ) -> Result<()> {
+    lint::ensure_valid(&selected, &app_paths.registry, &dest_path)?;
+    let working_files = &app_paths.working_files;
     let filename = selected
         .file_name()
         .and_then(|s| s.to_str())
         .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
 
-    // Destination markdown path
-    let dest_md = dest_path.files.join(filename);
+    // Destination markdown path, keeping any subdirectory below the working dir
+    let rel = selected
+        .strip_prefix(working_files)
+        .unwrap_or(Path::new(filename));
+    let dest_md = dest_path.files.join(rel);
     if dest_md.exists() {
         return Err(anyhow::anyhow!(
             "Destination markdown already exists: {}",
@@ -27,46 +44,52 @@ pub fn publish_selected(
     }
 
     // Copy markdown
-    fs::create_dir_all(&dest_path.files)?;
+    fs::create_dir_all(dest_md.parent().unwrap())?;
     fs::copy(&selected, &dest_md)
         .map_err(|e| anyhow::anyhow!("Failed to copy markdown to {}: {}", dest_md.display(), e))?;
 
     // Keep track of created files for rollback
     let mut created: Vec<PathBuf> = vec![dest_md.clone()];
 
-    // Copy images if configured
-    if let (Some(src_images), Some(dst_images)) = (working_images, dest_path.images) {
-        let stem = selected
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .ok_or_else(|| anyhow::anyhow!("Invalid filename stem"))?;
+    // Copy images and other assets the collection has a directory for
+    let asset_types = dest_path
+        .asset_roots()
+        .into_iter()
+        .flat_map(|(_, t)| t)
+        .collect::<Vec<_>>();
+    if let Some(src_images) = &app_paths.working_images
+        && !asset_types.is_empty()
+    {
+        let stem = post_stem(&selected)?;
         let stem_lower = stem.to_lowercase();
-        let images = fs_helpers::matching_images_for_stem(&stem_lower, &src_images)?;
-        if images.is_empty() {
+        let found = PostImages::find(src_images, Some(stem), &stem_lower, &asset_types)?;
+        let sub = dest_path.post_images_dir(stem);
+        if found.files.is_empty() {
             println!(
                 "No images matching '{}' found in {}",
                 stem,
                 src_images.display()
             );
         } else {
-            fs::create_dir_all(&dst_images)?;
-            for p in images {
-                let dest_img = dst_images.join(p.file_name().unwrap());
-                if dest_img.exists() {
-                    let failures = fs_helpers::rollback_remove_files(&created);
-                    if failures.is_empty() {
-                        return Err(anyhow::anyhow!(
-                            "Image already exists at destination {} — aborting",
-                            dest_img.display()
-                        ));
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "Image exists and rollback failures: {}",
-                            failures.join("; ")
-                        ));
-                    }
-                }
-                fs::copy(&p, &dest_img).map_err(|e| {
+            let optimize = dest_path.optimize.as_ref();
+            let mut renames: Vec<(String, String)> = Vec::new();
+            for p in &found.files {
+                let Some(dst_images) = dest_path.asset_dir(p) else {
+                    continue;
+                };
+                let original = images::slash_path(p.strip_prefix(src_images).unwrap_or(p));
+                let rel = published_rel(&found, p, sub.as_deref());
+                let dst_dir = dst_images.join(rel.parent().unwrap_or(Path::new("")));
+                let placed = images::published_image(p, optimize, dest_path.scrub_metadata)
+                    .and_then(|(name, bytes)| {
+                        let placement = images::place(&dst_dir, &name, &bytes)?;
+                        if let Placement::New(n) = &placement {
+                            fs::create_dir_all(&dst_dir)?;
+                            fs::write(dst_dir.join(n), &bytes)?;
+                        }
+                        Ok((name, placement, bytes.len()))
+                    });
+                let (name, placement, written) = placed.map_err(|e| {
                     let failures = fs_helpers::rollback_remove_files(&created);
                     if failures.is_empty() {
                         anyhow::anyhow!("Failed to copy image {}: {}", p.display(), e)
@@ -78,20 +101,111 @@ pub fn publish_selected(
                         )
                     }
                 })?;
-                created.push(dest_img);
+
+                let published = match placement {
+                    // already published by another post; nothing to copy
+                    Placement::Existing(n) => n,
+                    Placement::New(n) => {
+                        created.push(dst_dir.join(&n));
+                        if n != name {
+                            println!(
+                                "  {} already exists with different content; publishing as {}",
+                                name, n
+                            );
+                        }
+                        if optimize.is_some() {
+                            let before = fs::metadata(p).map(|m| m.len()).unwrap_or_default();
+                            println!(
+                                "  {} -> {} ({} -> {})",
+                                original,
+                                n,
+                                images::human_size(before),
+                                images::human_size(written as u64)
+                            );
+                        }
+                        n
+                    }
+                };
+                let published = images::slash_path(&rel.with_file_name(published));
+                renames.extend(routed_link(&dest_path, dst_images, &original, &published));
+                renames.push((original, published));
+            }
+
+            // Renamed or moved images (new format, name clash, per-post dir); point
+            // the post at their new paths
+            if renames.iter().any(|(old, new)| old != new) {
+                let rewritten = fs::read_to_string(&dest_md).and_then(|text| {
+                    fs::write(&dest_md, images::rewrite_references(&text, &renames))
+                });
+                if let Err(e) = rewritten {
+                    let failures = fs_helpers::rollback_remove_files(&created);
+                    return Err(anyhow::anyhow!(
+                        "Failed to update image references in {}: {}{}",
+                        dest_md.display(),
+                        e,
+                        if failures.is_empty() {
+                            String::new()
+                        } else {
+                            format!("; rollback failures: {}", failures.join("; "))
+                        }
+                    ));
+                }
             }
         }
     }
 
+    // Files the post links to next to it in the working dir
+    if !dest_path.colocated_assets.is_off() {
+        let placed = fs::read_to_string(&dest_md)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| {
+                let mut copies = Vec::new();
+                let mut renames = Vec::new();
+                colocated_copies(
+                    &selected,
+                    &dest_md,
+                    &dest_path,
+                    &app_paths.registry,
+                    None,
+                    &mut copies,
+                    &mut renames,
+                )?;
+                for (src, dst, bytes) in copies {
+                    // placed under a name that already holds the same content
+                    if dst.exists() {
+                        continue;
+                    }
+                    fs::create_dir_all(dst.parent().unwrap())?;
+                    fs::write(&dst, bytes)
+                        .map_err(|e| anyhow::anyhow!("Failed to copy {}: {}", src.display(), e))?;
+                    created.push(dst);
+                }
+                fs::write(&dest_md, images::rewrite_references(&text, &renames))?;
+                Ok(())
+            });
+        if let Err(e) = placed {
+            let failures = fs_helpers::rollback_remove_files(&created);
+            if failures.is_empty() {
+                return Err(e);
+            }
+            return Err(anyhow::anyhow!(
+                "{}; rollback failures: {}",
+                e,
+                failures.join("; ")
+            ));
+        }
+    }
+
     // Show summary and ask for confirmation
     println!("About to commit the following files:");
     for f in &created {
         println!("  {}", f.display());
     }
 
-    if !Confirm::new("Proceed to run git add/commit/push?")
-        .with_default(true)
-        .prompt()?
+    if confirm
+        && !Confirm::new("Proceed to run git add/commit/push?")
+            .with_default(true)
+            .prompt()?
     {
         let failures = fs_helpers::rollback_remove_files(&created);
         if failures.is_empty() {
@@ -125,85 +239,314 @@ pub fn publish_selected(
     Ok(())
 }
 
+/// Overwrite an already published file (and its images) with the working copy.
+pub fn update_selected(
+    selected: PathBuf,
+    app_paths: &AppPaths,
+    dest_path: CollectionPaths,
+) -> Result<()> {
+    let filename = selected
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
+    let rel = selected
+        .strip_prefix(&app_paths.working_files)
+        .unwrap_or(Path::new(filename));
+    let dest_md = dest_path.files.join(rel);
+    if !dest_md.exists() {
+        return Err(anyhow::anyhow!(
+            "{} is not published in '{}'; publish it first",
+            filename,
+            dest_path.name
+        ));
+    }
+    lint::ensure_valid(&selected, &app_paths.registry, &dest_path)?;
+
+    // Pair each source with its destination and the bytes to publish there;
+    // only changed or new files are written
+    let mut copies: Vec<FileCopy> = Vec::new();
+    let mut renames: Vec<(String, String)> = Vec::new();
+    let asset_types = dest_path
+        .asset_roots()
+        .into_iter()
+        .flat_map(|(_, t)| t)
+        .collect::<Vec<_>>();
+    if let Some(src_images) = &app_paths.working_images
+        && !asset_types.is_empty()
+    {
+        let stem = post_stem(&selected)?;
+        let found = PostImages::find(src_images, Some(stem), &stem.to_lowercase(), &asset_types)?;
+        let sub = dest_path.post_images_dir(stem);
+        let optimize = dest_path.optimize.as_ref();
+        let published_post = fs::read_to_string(&dest_md).unwrap_or_default();
+        for p in &found.files {
+            let Some(dst_images) = dest_path.asset_dir(p) else {
+                continue;
+            };
+            let rel = published_rel(&found, p, sub.as_deref());
+            let dst_dir = dst_images.join(rel.parent().unwrap_or(Path::new("")));
+            let (name, bytes) = images::published_image(p, optimize, dest_path.scrub_metadata)?;
+            // overwrite the post's own image; a name it doesn't use may belong to another post
+            let name = if sub.is_some() || images::references(&published_post, &name) {
+                name
+            } else {
+                match images::place(&dst_dir, &name, &bytes)? {
+                    Placement::New(n) | Placement::Existing(n) => n,
+                }
+            };
+            let original = images::slash_path(p.strip_prefix(src_images).unwrap_or(p));
+            let published = images::slash_path(&rel.with_file_name(&name));
+            renames.extend(routed_link(&dest_path, dst_images, &original, &published));
+            renames.push((original, published));
+            copies.push((p.clone(), dst_dir.join(name), bytes));
+        }
+    }
+    if !dest_path.colocated_assets.is_off() {
+        let published_post = fs::read_to_string(&dest_md).unwrap_or_default();
+        colocated_copies(
+            &selected,
+            &dest_md,
+            &dest_path,
+            &app_paths.registry,
+            Some(&published_post),
+            &mut copies,
+            &mut renames,
+        )?;
+    }
+    let post = fs::read(&selected)?;
+    let post = match String::from_utf8(post) {
+        Ok(text) => images::rewrite_references(&text, &renames).into_bytes(),
+        Err(e) => e.into_bytes(),
+    };
+    copies.insert(0, (selected.clone(), dest_md.clone(), post));
+    copies.retain(|(_, dst, bytes)| fs::read(dst).ok().as_ref() != Some(bytes));
+    if copies.is_empty() {
+        println!("{} is already up to date in '{}'", filename, dest_path.name);
+        return Ok(());
+    }
+
+    let overwritten: Vec<PathBuf> = copies
+        .iter()
+        .filter(|(_, dst, _)| dst.exists())
+        .map(|(_, dst, _)| dst.clone())
+        .collect();
+    let (backup_dir, backups) = fs_helpers::backup_files_to_temp(&overwritten)?;
+
+    let mut touched: Vec<PathBuf> = Vec::new();
+    let mut created: Vec<PathBuf> = Vec::new();
+    for (src, dst, bytes) in &copies {
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let existed = dst.exists();
+        if let Err(e) = fs::write(dst, bytes) {
+            fs_helpers::rollback_remove_files(&created);
+            restore_and_cleanup(&backups, &backup_dir)?;
+            return Err(anyhow::anyhow!("Failed to copy {}: {}", src.display(), e));
+        }
+        if !existed {
+            created.push(dst.clone());
+        }
+        touched.push(dst.clone());
+    }
+
+    println!("About to commit the following files:");
+    for f in &touched {
+        println!("  {}", f.display());
+    }
+
+    if !Confirm::new("Proceed to run git add/commit/push?")
+        .with_default(true)
+        .prompt()?
+    {
+        fs_helpers::rollback_remove_files(&created);
+        restore_and_cleanup(&backups, &backup_dir)?;
+        println!("Aborted by user; restored previous files.");
+        return Ok(());
+    }
+
+    let site_root = git_helpers::get_site_root(&dest_path.files);
+    if let Err(e) = git_helpers::run_git_steps(
+        &site_root,
+        &format!("Update {} in blog", filename),
+        &touched,
+    ) {
+        fs_helpers::rollback_remove_files(&created);
+        restore_and_cleanup(&backups, &backup_dir)?;
+        return Err(e);
+    }
+
+    fs_helpers::cleanup_backup_dir(&backup_dir);
+    println!("Updated {} successfully", filename);
+    Ok(())
+}
+
+/// Remove a published post with its images and co-located files, and commit.
+/// With `unpublish` they always go back to the working dir first.
 pub fn delete_selected(
     selected: PathBuf,
     path: CollectionPaths,
-    backup_dir: PathBuf,
-    working_images: Option<PathBuf>,
+    app_paths: &AppPaths,
+    only_referenced: bool,
+    unpublish: bool,
 ) -> Result<()> {
+    let backup_dir = &app_paths.working_files;
+    let working_images = &app_paths.working_images;
     let filename = selected
         .file_name()
         .and_then(|s| s.to_str())
         .ok_or_else(|| anyhow::anyhow!("Invalid filename"))?;
 
-    let stem = selected
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .ok_or_else(|| anyhow::anyhow!("Invalid filename stem"))?;
-    let stem_lower = stem.to_lowercase();
+    let stem = post_stem(&selected)?;
 
-    // Check if markdown exists in working dir
-    let working_md = backup_dir.join(filename);
+    // Warn about pages that would be left with a broken link
+    match LinkIndex::build(app_paths) {
+        Ok(index) => {
+            let inbound = index.inbound(&selected);
+            if !inbound.is_empty() {
+                println!("{} is linked from:", filename);
+                for l in &inbound {
+                    println!("  {}:{} -> {}", l.source.display(), l.line, l.target);
+                }
+                if !Confirm::new("Delete anyway? These links will break.")
+                    .with_default(false)
+                    .prompt()?
+                {
+                    println!("Aborted by user; nothing deleted.");
+                    return Ok(());
+                }
+            }
+        }
+        Err(e) => println!("Warning: could not check links to {}: {}", filename, e),
+    }
+
+    // Published images and assets: the post's own dir in a per-post collection,
+    // or files named after it, in every asset dir
+    let published = PostImages::in_collection(&path, stem)?;
+    let is_published = |file: &PathBuf| published.iter().any(|p| p.files.contains(file));
+
+    // Files the post links to that were published as co-located assets
+    let text = fs::read_to_string(&selected).unwrap_or_default();
+    let mut colocated: Vec<(String, PathBuf)> = Vec::new();
+    match path.colocated_assets {
+        ColocatedAssets::Content => colocated.extend(
+            assets::colocated(&selected, &text, &app_paths.registry)
+                .into_iter()
+                .filter(|(_, file)| path.takes_asset(file)),
+        ),
+        ColocatedAssets::Images => {
+            for (dir, _) in path.asset_roots() {
+                let url = assets::public_url(dir, &path.files)?;
+                colocated.extend(assets::linked_images(&text, dir, &url, &app_paths.registry));
+            }
+        }
+        ColocatedAssets::Off => {}
+    }
+    colocated.retain(|(_, file)| !is_published(file));
+
+    // Images other files still refer to stay; with `only_referenced`, so do the
+    // ones named after the post that it doesn't refer to
+    let refs = ImageRefs::build(app_paths)?;
+    let roots = path.asset_roots();
+    let mut kept: Vec<(PathBuf, String)> = Vec::new();
+    let candidates = published
+        .iter()
+        .flat_map(|p| p.files.iter())
+        .chain(colocated.iter().map(|(_, f)| f));
+    for file in candidates {
+        let dir = match path.colocated_assets {
+            ColocatedAssets::Content if !is_published(file) => selected.parent(),
+            _ => roots
+                .iter()
+                .map(|(d, _)| d.as_path())
+                .find(|d| file.starts_with(d)),
+        };
+        if let Some(dir) = dir
+            && let Some(reason) = refs.keep_reason(dir, file, &selected, only_referenced)
+        {
+            kept.push((file.clone(), reason));
+        }
+    }
 
-    let mut backup_files: Vec<PathBuf> = Vec::new();
+    // Check if markdown exists in working dir
+    let rel = selected
+        .strip_prefix(&path.files)
+        .unwrap_or(Path::new(filename));
+    let working_md = backup_dir.join(rel);
 
-    if !working_md.exists() {
+    // Whether to copy the published files back, whether the post itself goes
+    // too, and what happens to working files that differ: a working copy that
+    // differs is never overwritten without asking
+    let (restore, write_post, existing) = if !working_md.exists() {
         let ask = format!(
             "'{}' not found in working dir. Create backup in working dir?",
             filename
         );
-        if Confirm::new(&ask).with_default(true).prompt()? {
-            let copied = fs_helpers::copy_file_to(&selected.to_path_buf(), &backup_dir)?;
-            backup_files.push(copied.clone());
-
-            if let (Some(pub_imgs), Some(work_imgs)) = (&path.images, &working_images) {
-                let images = fs_helpers::matching_images_for_stem(&stem_lower, pub_imgs)?;
-                if !images.is_empty() {
-                    fs::create_dir_all(work_imgs)?;
-                    for img in images.iter() {
-                        let dest = work_imgs.join(img.file_name().unwrap());
-                        if dest.exists() {
-                            for f in &backup_files {
-                                let _ = fs::remove_file(f);
-                            }
-                            return Err(anyhow::anyhow!(
-                                "Backup target already exists: {}",
-                                dest.display()
-                            ));
-                        }
-                        fs::copy(img, &dest).map_err(|e| {
-                            for f in &backup_files {
-                                let _ = fs::remove_file(f);
-                            }
-                            anyhow::anyhow!(
-                                "Failed to copy image {} to {}: {}",
-                                img.display(),
-                                dest.display(),
-                                e
-                            )
-                        })?;
-                        backup_files.push(dest);
-                    }
-                }
-            }
-
-            println!("Backup created in {}", backup_dir.display());
+        let create = unpublish || Confirm::new(&ask).with_default(true).prompt()?;
+        (create, create, Existing::Keep)
+    } else if same_post(
+        &fs::read_to_string(&working_md)?,
+        &text,
+        &app_paths.registry,
+    ) {
+        println!(
+            "{} in the working dir matches the published copy.",
+            filename
+        );
+        (true, false, Existing::Keep)
+    } else {
+        print!("{}", git_helpers::diff_files(&working_md, &selected)?);
+        let keep = "Keep the working copy";
+        let replace = "Replace the working copy with the published one";
+        let keep_both = format!(
+            "Keep both; save the published one as {}",
+            published_copy_name(&working_md)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+        );
+        let prompt = format!(
+            "{} in the working dir differs from the published copy:",
+            filename
+        );
+        let choice = Select::new(&prompt, vec![keep, replace, &keep_both]).prompt()?;
+        if choice == keep {
+            (true, false, Existing::Keep)
+        } else if choice == replace {
+            (true, true, Existing::Replace)
         } else {
-            println!("Proceeding without backup.");
+            (true, true, Existing::KeepBoth)
         }
-    } else {
-        println!("File exists in working dir; skipping backup.");
-    }
+    };
 
-    // Gather list of images to delete in publishing_images
-    let mut to_delete: Vec<PathBuf> = Vec::new();
-    to_delete.push(selected.to_path_buf());
-    if let Some(pub_imgs) = &path.images {
-        let images = fs_helpers::matching_images_for_stem(&stem_lower, pub_imgs)?;
-        for img in images {
-            to_delete.push(img);
+    // The post, its published images and its co-located assets
+    let mut to_delete: Vec<PathBuf> = vec![selected.to_path_buf()];
+    to_delete.extend(published.iter().flat_map(|p| p.files.iter().cloned()));
+    for (_, file) in &colocated {
+        if !to_delete.contains(file) {
+            to_delete.push(file.clone());
         }
     }
+    to_delete.retain(|f| !kept.iter().any(|(k, _)| k == f));
+    if !kept.is_empty() {
+        println!("Keeping:");
+        for (file, reason) in &kept {
+            println!("  {} ({})", file.display(), reason);
+        }
+    }
+
+    // With a redirects file, the old URL can be sent somewhere else
+    let site_root = git_helpers::get_site_root(&path.files);
+    let old_route = links::route_of(&path, &selected);
+    let redirect = match &app_paths.redirects {
+        Some(redirects) => Text::new(&format!("Redirect {} to (empty for none):", old_route))
+            .with_initial_value(&links::collection_route(&path))
+            .prompt_skippable()?
+            .map(|to| to.trim().to_string())
+            .filter(|to| !to.is_empty())
+            .map(|to| (redirects, to)),
+        None => None,
+    };
 
     let (backup_dir, backups) = fs_helpers::backup_files_to_temp(&to_delete)?;
 
@@ -211,6 +554,9 @@ pub fn delete_selected(
     for p in &to_delete {
         println!("  {}", p.display());
     }
+    if let Some((_, to)) = &redirect {
+        println!("Redirecting {} -> {}", old_route, to);
+    }
     println!("Backups created at: {}", backup_dir.display());
 
     // Ask for confirmation
@@ -219,38 +565,411 @@ pub fn delete_selected(
         .prompt()?
     {
         cleanup_and_abort(&backup_dir, &backups)?;
-        println!("Aborted by user; backups at {}", backup_dir.display());
+        println!("Aborted by user; nothing deleted.");
         return Ok(());
     }
 
+    // Back into the working dir now that nothing can be cancelled anymore
+    let mut restored = Restore::new(existing);
+    let backed_up = (|| -> Result<()> {
+        if !restore {
+            println!("Proceeding without backup.");
+            return Ok(());
+        }
+        // Published references become references to the working copies
+        let mut relinks: Vec<(String, String)> = Vec::new();
+        if let Some(work_imgs) = working_images {
+            relinks = restore_images(&path, &published, work_imgs, stem, &mut restored)?;
+        }
+
+        // Co-located assets go back next to the post: at the same relative path,
+        // or into a folder named after it when they were moved to the images dir
+        let post_dir = working_md.parent().unwrap();
+        for (target, file) in &colocated {
+            let dest = match path.colocated_assets {
+                ColocatedAssets::Content => post_dir.join(
+                    file.strip_prefix(selected.parent().unwrap())
+                        .unwrap_or(file),
+                ),
+                _ => {
+                    let name = file.file_name().unwrap();
+                    // an index file already lives in the post's folder
+                    if selected.file_stem().and_then(|s| s.to_str()) == Some(stem) {
+                        post_dir.join(stem).join(name)
+                    } else {
+                        post_dir.join(name)
+                    }
+                }
+            };
+            let copied = restored.copy(file, &dest)?;
+            if path.colocated_assets != ColocatedAssets::Content || copied != dest {
+                let link = images::slash_path(copied.strip_prefix(post_dir).unwrap_or(&copied));
+                relinks.push((target.clone(), link));
+            }
+        }
+
+        // the post last, so a failed backup never leaves the working copy replaced
+        if write_post {
+            let text = images::rewrite_references(&text, &relinks);
+            restored
+                .write(&working_md, text.as_bytes())
+                .map_err(|e| anyhow::anyhow!("Failed to copy {}: {}", filename, e))?;
+        }
+        println!("Backup created in {}", app_paths.working_files.display());
+        Ok(())
+    })();
+    if let Err(e) = backed_up {
+        restored.undo();
+        cleanup_and_abort(&backup_dir, &backups)?;
+        return Err(e);
+    }
+
     // Delete files
     for p in &to_delete {
         if p.exists()
             && let Err(e) = fs::remove_file(p)
         {
+            restored.undo();
             restore_and_cleanup(&backups, &backup_dir)?;
             return Err(anyhow::anyhow!("Failed to remove {}: {}", p.display(), e));
         }
     }
+    for dir in published.iter().filter_map(|p| p.own_dir.as_ref()) {
+        fs_helpers::remove_empty_dirs(dir);
+    }
+    if let Some(post_dir) = selected.parent() {
+        for (_, file) in &colocated {
+            if let Some(dir) = file.parent()
+                && dir != post_dir
+                && dir.starts_with(post_dir)
+            {
+                fs_helpers::remove_empty_dirs(dir);
+            }
+        }
+    }
 
-    // Run git steps
-    let site_root = git_helpers::get_site_root(&path.files);
-    if let Err(e) = git_helpers::run_git_steps(
-        &site_root,
-        &format!("Remove {} from blog", filename),
-        &to_delete,
-    ) {
+    // Run git steps, with the redirect in the same commit
+    let mut git_paths = to_delete.clone();
+    let redirects_before = redirect
+        .as_ref()
+        .map(|(r, _)| (r.path(&site_root), fs::read(r.path(&site_root)).ok()));
+    let result = match &redirect {
+        Some((redirects, to)) => redirects
+            .add(&site_root, &old_route, to)
+            .map(|file| git_paths.push(file)),
+        None => Ok(()),
+    }
+    .and_then(|_| {
+        git_helpers::run_git_steps(
+            &site_root,
+            &if unpublish {
+                format!("Unpublish {} from {}", filename, path.name)
+            } else {
+                format!("Remove {} from blog", filename)
+            },
+            &git_paths,
+        )
+    });
+    if let Err(e) = result {
         if let Err(rest_err) = fs_helpers::restore_from_backups(&backups) {
             eprintln!("Failed to restore from backups: {}", rest_err);
         }
+        restored.undo();
+        match redirects_before {
+            Some((file, Some(bytes))) => {
+                let _ = fs::write(file, bytes);
+            }
+            Some((file, None)) => {
+                let _ = fs::remove_file(file);
+            }
+            None => {}
+        }
         fs_helpers::cleanup_backup_dir(&backup_dir);
         return Err(e);
     }
 
     fs_helpers::cleanup_backup_dir(&backup_dir);
-    println!("Deleted {} and corresponding images", filename);
+    if unpublish {
+        println!(
+            "Unpublished {}; it's back in {}",
+            filename,
+            app_paths.working_files.display()
+        );
+    } else {
+        println!("Deleted {} and corresponding images", filename);
+    }
+    Ok(())
+}
+/// What a delete does with a working file that differs from the published one
+/// it would copy back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Existing {
+    Keep,
+    Replace,
+    /// Save the published one next to it as `<name>.published.<ext>`
+    KeepBoth,
+}
+
+/// Copies published files back into the working dir, remembering what it
+/// created and overwrote so it can all be undone.
+struct Restore {
+    existing: Existing,
+    created: Vec<PathBuf>,
+    replaced: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl Restore {
+    fn new(existing: Existing) -> Self {
+        Self {
+            existing,
+            created: Vec::new(),
+            replaced: Vec::new(),
+        }
+    }
+
+    /// Copy `src` to `dest`; returns where its content now is in the working dir.
+    fn copy(&mut self, src: &Path, dest: &Path) -> Result<PathBuf> {
+        let bytes = fs::read(src)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", src.display(), e))?;
+        self.write(dest, &bytes)
+    }
+
+    /// Write `bytes` to `dest`, or as `existing` says when a different file is
+    /// already there; returns the file that now stands for them.
+    fn write(&mut self, dest: &Path, bytes: &[u8]) -> Result<PathBuf> {
+        let dest = match fs::read(dest) {
+            Ok(old) if old == bytes => return Ok(dest.to_path_buf()),
+            Ok(_) if self.existing == Existing::Keep => {
+                println!(
+                    "  kept {} (differs from the published copy)",
+                    dest.display()
+                );
+                return Ok(dest.to_path_buf());
+            }
+            Ok(_) if self.existing == Existing::KeepBoth => published_copy_name(dest),
+            _ => dest.to_path_buf(),
+        };
+        match fs::read(&dest) {
+            Ok(old) if old == bytes => return Ok(dest),
+            Ok(old) => self.replaced.push((dest.clone(), old)),
+            Err(_) => self.created.push(dest.clone()),
+        }
+        fs::create_dir_all(dest.parent().unwrap())
+            .and_then(|_| fs::write(&dest, bytes))
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", dest.display(), e))?;
+        Ok(dest)
+    }
+
+    /// Put the working dir back the way it was.
+    fn undo(&self) {
+        let _ = fs_helpers::rollback_remove_files(&self.created);
+        for (file, old) in &self.replaced {
+            let _ = fs::write(file, old);
+        }
+    }
+}
+
+/// `post.md` -> `post.published.md`, `Makefile` -> `Makefile.published`
+fn published_copy_name(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}.published.{}", stem, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}.published", stem)),
+    }
+}
+
+/// Copy the post's published images and assets back into the working images
+/// dir: into `<stem>/` when they had a folder of their own. Returns the link
+/// changes that point the published post at the copies, undoing the ones made
+/// on publish (per-post folders, routed asset dirs).
+fn restore_images(
+    c: &CollectionPaths,
+    published: &[PostImages],
+    work_imgs: &Path,
+    stem: &str,
+    restored: &mut Restore,
+) -> Result<Vec<(String, String)>> {
+    let images_url = c
+        .images
+        .as_ref()
+        .and_then(|i| assets::public_url(i, &c.files).ok());
+    let mut relinks = Vec::new();
+    for ((root, _), found) in c.asset_roots().into_iter().zip(published) {
+        for img in &found.files {
+            let dest = match &found.own_dir {
+                Some(_) => work_imgs.join(stem).join(found.inner(img)),
+                None => work_imgs.join(img.file_name().unwrap()),
+            };
+            let copied = restored.copy(img, &dest)?;
+            let old = images::slash_path(img.strip_prefix(root).unwrap_or(img));
+            let new = images::slash_path(copied.strip_prefix(work_imgs).unwrap_or(&copied));
+            if let (Ok(url), Some(images_url)) = (assets::public_url(root, &c.files), &images_url)
+                && url != *images_url
+            {
+                relinks.push((
+                    format!("{}/{}", url, old),
+                    format!("{}/{}", images_url, new),
+                ));
+            }
+            relinks.push((old, new));
+        }
+    }
+    Ok(relinks)
+}
+
+/// Whether a working file and its published copy hold the same content; text
+/// files are compared with [`same_post`], anything else byte for byte.
+pub(crate) fn same_file(working: &Path, published: &Path, registry: &Registry) -> bool {
+    match (fs::read_to_string(working), fs::read_to_string(published)) {
+        (Ok(w), Ok(p)) => same_post(&w, &p, registry),
+        _ => fs::read(working).ok() == fs::read(published).ok(),
+    }
+}
+
+/// Whether a working and a published post say the same once their image and
+/// asset references are set aside, since publishing may rename and move those.
+pub(crate) fn same_post(working: &str, published: &str, registry: &Registry) -> bool {
+    let keyed = |text: &str| {
+        let renames: Vec<(String, String)> = links::extract_links(text, registry)
+            .into_iter()
+            .filter(|(_, target, image)| {
+                *image
+                    || registry
+                        .kind_of(Path::new(links::strip_suffixes(target)))
+                        .is_some_and(|k| !k.is_content())
+            })
+            .map(|(_, target, _)| {
+                let key = format!("asset:{}", asset_key(&target));
+                (target, key)
+            })
+            .collect();
+        images::rewrite_references(text, &renames)
+    };
+    keyed(working) == keyed(published)
+}
+
+/// What publishing leaves of an asset's name: `/images/hi/Cover-1a2b3c4d.webp`
+/// and `Hi/cover.png` are both `cover`.
+fn asset_key(target: &str) -> String {
+    let path = links::strip_suffixes(target);
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let stem = match stem.rsplit_once('-') {
+        Some((base, hash)) if hash.len() == 8 && hash.chars().all(|c| c.is_ascii_hexdigit()) => {
+            base
+        }
+        _ => stem,
+    };
+    stem.to_lowercase()
+}
+
+/// The name a post's images go by: its file stem, or the folder name of an
+/// `index` file such as `post/index.md`.
+pub fn post_stem(path: &Path) -> Result<&str> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid filename stem"))?;
+    if stem.eq_ignore_ascii_case("index")
+        && let Some(dir) = path.parent().and_then(|d| d.file_name()?.to_str())
+    {
+        return Ok(dir);
+    }
+    Ok(stem)
+}
+
+/// Add the files the working post `selected` links to by a relative path, and
+/// `dest` takes, to `copies`, and the changes to its links to `renames`. On update,
+/// `published_post` is the text already published and the post's own files are
+/// overwritten; otherwise a name that's taken gets a hash.
+fn colocated_copies(
+    selected: &Path,
+    dest_md: &Path,
+    dest: &CollectionPaths,
+    registry: &Registry,
+    published_post: Option<&str>,
+    copies: &mut Vec<FileCopy>,
+    renames: &mut Vec<(String, String)>,
+) -> Result<()> {
+    let stem = post_stem(selected)?;
+    let src_dir = selected.parent().unwrap_or(Path::new(""));
+    let optimize = dest.optimize.as_ref();
+    let text = fs::read_to_string(selected)?;
+    for (target, file) in assets::colocated(selected, &text, registry) {
+        if !dest.takes_asset(&file) {
+            println!(
+                "  {} is not one of '{}' assets; not copied",
+                file.display(),
+                dest.name
+            );
+            continue;
+        }
+        let (path, suffix) = target.split_at(target.find(['#', '?']).unwrap_or(target.len()));
+        // destination dir, and what the link says before the file name
+        let (dir, prefix) = match dest.colocated_assets {
+            ColocatedAssets::Images => {
+                let images = dest.asset_dir(&file).ok_or_else(|| {
+                    anyhow::anyhow!("Collection '{}' has no images dir", dest.name)
+                })?;
+                let url = assets::public_url(images, &dest.files)?;
+                match dest.post_images_dir(stem) {
+                    Some(sub) => (images.join(&sub), format!("{}/{}/", url, sub)),
+                    None => (images.clone(), format!("{}/", url)),
+                }
+            }
+            _ => {
+                let rel = file.strip_prefix(src_dir).unwrap_or(&file);
+                let dir = dest_md
+                    .parent()
+                    .unwrap_or(Path::new(""))
+                    .join(rel.parent().unwrap_or(Path::new("")));
+                (
+                    dir,
+                    path[..path.rfind('/').map_or(0, |i| i + 1)].to_string(),
+                )
+            }
+        };
+
+        let (name, bytes) = images::published_image(&file, optimize, dest.scrub_metadata)?;
+        let owned = dest.colocated_assets == ColocatedAssets::Content
+            || dest.per_post_images
+            || published_post.is_some_and(|t| images::references(t, &name));
+        let name = if published_post.is_some() && owned {
+            name
+        } else {
+            match images::place(&dir, &name, &bytes)? {
+                Placement::New(n) | Placement::Existing(n) => n,
+            }
+        };
+        renames.push((target.clone(), format!("{}{}{}", prefix, name, suffix)));
+        let dst = dir.join(&name);
+        if !copies.iter().any(|(_, d, _)| *d == dst) {
+            copies.push((file, dst, bytes));
+        }
+    }
     Ok(())
 }
+
+/// Where `file`, one of a post's working images, goes below the collection's
+/// images dir: `<sub>/<path in the post's dir>`, or just its name when the
+/// collection keeps images side by side.
+fn published_rel(found: &PostImages, file: &Path, sub: Option<&str>) -> PathBuf {
+    match sub {
+        Some(sub) => Path::new(sub).join(found.inner(file)),
+        None => PathBuf::from(file.file_name().unwrap_or_default()),
+    }
+}
+
+/// Link change for an asset published to its own type's dir rather than the
+/// images dir: `/images/demo.mp4` -> `/video/demo.mp4`. None when it stays in
+/// the images dir or either dir isn't served from the site's `public/`.
+fn routed_link(c: &CollectionPaths, dir: &Path, old: &str, new: &str) -> Option<(String, String)> {
+    let images = c.images.as_ref().filter(|i| i.as_path() != dir)?;
+    let from = assets::public_url(images, &c.files).ok()?;
+    let to = assets::public_url(dir, &c.files).ok()?;
+    Some((format!("{}/{}", from, old), format!("{}/{}", to, new)))
+}
+
 fn cleanup_and_abort(backup_dir: &PathBuf, backups: &[(PathBuf, PathBuf)]) -> Result<()> {
     // remove temp backups to avoid clutter on cancel
     for (_, backup) in backups {
@@ -266,3 +985,174 @@ fn restore_and_cleanup(backups: &[(PathBuf, PathBuf)], backup_dir: &PathBuf) ->
     fs_helpers::cleanup_backup_dir(backup_dir);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::FileFilter;
+    use crate::schema::Schema;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    /// `blog` in the site at `site`, with mp4 files routed to `public/video`.
+    fn blog(site: &Path) -> CollectionPaths {
+        CollectionPaths {
+            name: "blog".to_string(),
+            files: site.join("content/blog"),
+            images: Some(site.join("public/images")),
+            filter: FileFilter::default(),
+            prefix: None,
+            schema: Schema::default(),
+            optimize: None,
+            scrub_metadata: false,
+            per_post_images: false,
+            colocated_assets: ColocatedAssets::Images,
+            asset_types: vec!["png".to_string(), "mp4".to_string()],
+            asset_dirs: BTreeMap::from([("mp4".to_string(), site.join("public/video"))]),
+        }
+    }
+
+    #[test]
+    fn routed_assets_get_their_dirs_url() {
+        let site = Path::new("/site");
+        let c = blog(site);
+        assert_eq!(
+            routed_link(&c, &site.join("public/video"), "demo.mp4", "demo.mp4"),
+            Some((
+                "/images/demo.mp4".to_string(),
+                "/video/demo.mp4".to_string()
+            ))
+        );
+        assert_eq!(
+            routed_link(&c, &site.join("public/images"), "a.png", "a.png"),
+            None
+        );
+    }
+
+    #[test]
+    fn colocated_assets_follow_the_collections_types_and_dirs() {
+        let td = tempdir().unwrap();
+        let site = td.path().join("site");
+        let c = blog(&site);
+        fs::create_dir_all(td.path().join("work/post")).unwrap();
+        for name in ["post/demo.mp4", "post/cover.png", "post/slides.pdf"] {
+            fs::write(td.path().join("work").join(name), b"x").unwrap();
+        }
+        let post = td.path().join("work/post.md");
+        fs::write(
+            &post,
+            "![v](post/demo.mp4) ![c](post/cover.png) [s](post/slides.pdf)\n",
+        )
+        .unwrap();
+
+        let mut copies = Vec::new();
+        let mut renames = Vec::new();
+        colocated_copies(
+            &post,
+            &c.files.join("post.md"),
+            &c,
+            &Registry::default(),
+            None,
+            &mut copies,
+            &mut renames,
+        )
+        .unwrap();
+        let dests: Vec<&PathBuf> = copies.iter().map(|(_, d, _)| d).collect();
+        assert_eq!(
+            dests,
+            [
+                &site.join("public/video/demo.mp4"),
+                &site.join("public/images/cover.png")
+            ]
+        );
+        assert_eq!(
+            renames,
+            [
+                ("post/demo.mp4".to_string(), "/video/demo.mp4".to_string()),
+                (
+                    "post/cover.png".to_string(),
+                    "/images/cover.png".to_string()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn a_working_copy_is_the_same_despite_published_image_names() {
+        let working = "---\ncover: /images/hi-cover.png\n---\n\
+![a](/images/hi-cover.png) [v](/images/hi.mp4) ![d](hi/diagram.svg)\n";
+        let published = "---\ncover: /images/hi/hi-cover.webp\n---\n\
+![a](/images/hi/hi-cover.webp) [v](/video/hi.mp4) ![d](/images/diagram-1a2b3c4d.svg)\n";
+        let registry = Registry::default();
+        assert!(same_post(working, published, &registry));
+        assert!(!same_post(
+            &working.replace("![a]", "![b]"),
+            published,
+            &registry
+        ));
+        assert!(!same_post(
+            working,
+            &published.replace("hi-cover.webp", "other.webp"),
+            &registry
+        ));
+    }
+
+    #[test]
+    fn kept_both_copies_are_named_published() {
+        assert_eq!(
+            published_copy_name(Path::new("/w/post.md")),
+            PathBuf::from("/w/post.published.md")
+        );
+        assert_eq!(
+            published_copy_name(Path::new("/w/Makefile")),
+            PathBuf::from("/w/Makefile.published")
+        );
+
+        let td = tempdir().unwrap();
+        let src = td.path().join("published.png");
+        let dest = td.path().join("cover.png");
+        fs::write(&src, b"published").unwrap();
+        fs::write(&dest, b"working").unwrap();
+        let mut restored = Restore::new(Existing::KeepBoth);
+        let copied = restored.copy(&src, &dest).unwrap();
+        assert_eq!(copied, td.path().join("cover.published.png"));
+        assert_eq!(fs::read(&dest).unwrap(), b"working");
+        assert_eq!(fs::read(&copied).unwrap(), b"published");
+
+        let mut replaced = Restore::new(Existing::Replace);
+        assert_eq!(replaced.copy(&src, &dest).unwrap(), dest);
+        assert_eq!(fs::read(&dest).unwrap(), b"published");
+        replaced.undo();
+        restored.undo();
+        assert_eq!(fs::read(&dest).unwrap(), b"working");
+        assert!(!copied.exists());
+    }
+
+    #[test]
+    fn unpublished_images_go_back_into_the_posts_folder() {
+        let td = tempdir().unwrap();
+        let site = td.path().join("site");
+        let mut c = blog(&site);
+        c.per_post_images = true;
+        let own = site.join("public/images/hello");
+        fs::create_dir_all(own.join("shots")).unwrap();
+        fs::create_dir_all(site.join("public/video/hello")).unwrap();
+        fs::write(own.join("cover.webp"), b"c").unwrap();
+        fs::write(own.join("shots/one.png"), b"1").unwrap();
+        fs::write(site.join("public/video/hello/demo.mp4"), b"v").unwrap();
+        c.asset_types.push("webp".to_string());
+
+        let work = td.path().join("work/images");
+        let published = PostImages::in_collection(&c, "Hello").unwrap();
+        let mut restored = Restore::new(Existing::Keep);
+        let relinks = restore_images(&c, &published, &work, "Hello", &mut restored).unwrap();
+        for f in ["Hello/cover.webp", "Hello/shots/one.png", "Hello/demo.mp4"] {
+            assert!(work.join(f).is_file(), "{}", f);
+        }
+        let text = "![c](/images/hello/cover.webp) [v](/video/hello/demo.mp4)";
+        assert_eq!(
+            images::rewrite_references(text, &relinks),
+            "![c](/images/Hello/cover.webp) [v](/images/Hello/demo.mp4)"
+        );
+    }
+}
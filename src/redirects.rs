@@ -0,0 +1,178 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `[redirects]`: the site's redirects file, kept up to date by `delete` and `move`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RedirectsConfig {
+    #[serde(default)]
+    pub format: RedirectFormat,
+    /// Path relative to the site root; defaults to where the format is usually kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+/// How redirects are written.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RedirectFormat {
+    /// Nuxt `routeRules` as JSON, in `redirects.json`
+    #[default]
+    Nuxt,
+    /// Netlify `_redirects` lines, in `public/_redirects`
+    Netlify,
+    /// The `redirects` array of `vercel.json`
+    Vercel,
+}
+
+impl RedirectsConfig {
+    /// The redirects file of the site at `site_root`.
+    pub fn path(&self, site_root: &Path) -> PathBuf {
+        let default = match self.format {
+            RedirectFormat::Nuxt => "redirects.json",
+            RedirectFormat::Netlify => "public/_redirects",
+            RedirectFormat::Vercel => "vercel.json",
+        };
+        site_root.join(self.file.as_deref().unwrap_or(default))
+    }
+
+    /// Redirect `from` to `to` in the site's redirects file, creating it if needed.
+    /// Existing redirects to `from` are pointed at `to` as well, so no chains form,
+    /// and any redirect away from `to` is dropped.
+    pub fn add(&self, site_root: &Path, from: &str, to: &str) -> Result<PathBuf> {
+        let path = self.path(site_root);
+        let old = fs::read_to_string(&path).unwrap_or_default();
+        let new = match self.format {
+            RedirectFormat::Nuxt => add_route_rule(&old, from, to),
+            RedirectFormat::Netlify => Ok(add_netlify(&old, from, to)),
+            RedirectFormat::Vercel => add_vercel(&old, from, to),
+        }
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, new)?;
+        Ok(path)
+    }
+}
+
+fn add_route_rule(old: &str, from: &str, to: &str) -> Result<String> {
+    let mut rules: Map<String, Value> = if old.trim().is_empty() {
+        Map::new()
+    } else {
+        serde_json::from_str(old)?
+    };
+    for rule in rules.values_mut() {
+        if let Some(target) = rule.pointer_mut("/redirect/to")
+            && target == from
+        {
+            *target = json!(to);
+        }
+    }
+    // other rules for `to` (prerender, swr, ...) stay; only a redirect away goes
+    if rules.get(to).is_some_and(|r| r.get("redirect").is_some()) {
+        rules.remove(to);
+    }
+    rules.insert(
+        from.to_string(),
+        json!({ "redirect": { "to": to, "statusCode": 301 } }),
+    );
+    Ok(format!("{}\n", serde_json::to_string_pretty(&rules)?))
+}
+
+/// `/old /new 301` lines; comments and other rules are kept as they are.
+fn add_netlify(old: &str, from: &str, to: &str) -> String {
+    let mut out = String::new();
+    for line in old.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [src, ..] if *src == from || *src == to => continue,
+            [src, dest, rest @ ..] if *dest == from => {
+                out.push_str(&format!("{} {}", src, to));
+                for field in rest {
+                    out.push_str(&format!(" {}", field));
+                }
+            }
+            _ => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    out.push_str(&format!("{} {} 301\n", from, to));
+    out
+}
+
+/// Entries in the `redirects` array; the rest of `vercel.json` is kept.
+fn add_vercel(old: &str, from: &str, to: &str) -> Result<String> {
+    let mut config: Map<String, Value> = if old.trim().is_empty() {
+        Map::new()
+    } else {
+        serde_json::from_str(old)?
+    };
+    let redirects = config
+        .entry("redirects")
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+        .ok_or_else(|| anyhow::anyhow!("`redirects` is not an array"))?;
+    redirects.retain(|r| r["source"] != from && r["source"] != to);
+    for r in redirects.iter_mut() {
+        if r["destination"] == from {
+            r["destination"] = json!(to);
+        }
+    }
+    redirects.push(json!({ "source": from, "destination": to, "permanent": true }));
+    Ok(format!("{}\n", serde_json::to_string_pretty(&config)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn redirects_are_added_without_chains() {
+        let td = tempdir().unwrap();
+        let cfg = RedirectsConfig::default();
+        cfg.add(td.path(), "/blog/a", "/blog/b").unwrap();
+        cfg.add(td.path(), "/blog/b", "/news/c").unwrap();
+        let rules: Value =
+            serde_json::from_str(&fs::read_to_string(td.path().join("redirects.json")).unwrap())
+                .unwrap();
+        assert_eq!(rules["/blog/a"]["redirect"]["to"], "/news/c");
+        assert_eq!(rules["/blog/b"]["redirect"]["to"], "/news/c");
+        assert_eq!(rules["/blog/b"]["redirect"]["statusCode"], 301);
+    }
+
+    #[test]
+    fn other_route_rules_for_the_target_are_kept() {
+        let old = r#"{"/news/c": {"prerender": true}, "/blog/d": {"redirect": {"to": "/blog/a"}}}"#;
+        let rules: Value =
+            serde_json::from_str(&add_route_rule(old, "/blog/a", "/news/c").unwrap()).unwrap();
+        assert_eq!(rules["/news/c"]["prerender"], true);
+        assert_eq!(rules["/blog/d"]["redirect"]["to"], "/news/c");
+
+        let rules: Value =
+            serde_json::from_str(&add_route_rule(old, "/news/c", "/blog/d").unwrap()).unwrap();
+        assert_eq!(rules["/news/c"]["redirect"]["to"], "/blog/d");
+        assert!(rules.get("/blog/d").is_none());
+    }
+
+    #[test]
+    fn netlify_and_vercel_files_keep_their_other_rules() {
+        let netlify = "# moved\n/old /blog/a 302\n/shop https://shop.example.com 301!\n";
+        assert_eq!(
+            add_netlify(netlify, "/blog/a", "/blog/b"),
+            "# moved\n/old /blog/b 302\n/shop https://shop.example.com 301!\n/blog/a /blog/b 301\n"
+        );
+
+        let vercel =
+            r#"{"cleanUrls": true, "redirects": [{"source": "/old", "destination": "/blog/a"}]}"#;
+        let out: Value =
+            serde_json::from_str(&add_vercel(vercel, "/blog/a", "/blog/b").unwrap()).unwrap();
+        assert_eq!(out["cleanUrls"], true);
+        assert_eq!(out["redirects"][0]["destination"], "/blog/b");
+        assert_eq!(out["redirects"][1]["source"], "/blog/a");
+        assert_eq!(out["redirects"][1]["permanent"], true);
+    }
+}
@@ -0,0 +1,241 @@
+use anyhow::Result;
+use inquire::Confirm;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::AppPaths;
+use crate::fs as fs_helpers;
+use crate::git as git_helpers;
+use crate::images;
+
+/// Text of every collection file, plus the other files git tracks in their
+/// sites (pages, components, config), to look up which of them refer to an image.
+pub struct ImageRefs {
+    texts: Vec<(PathBuf, String)>,
+}
+
+impl ImageRefs {
+    pub fn build(app_paths: &AppPaths) -> Result<Self> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        let mut sites: Vec<PathBuf> = Vec::new();
+        for c in &app_paths.collections {
+            files.extend(c.filter.list(&c.files)?);
+            let site_root = git_helpers::get_site_root(&c.files);
+            if !sites.contains(&site_root) {
+                sites.push(site_root);
+            }
+        }
+        for site_root in &sites {
+            let public = site_root.join("public");
+            files.extend(
+                git_helpers::tracked_files(site_root)
+                    .into_iter()
+                    .filter(|f| !f.starts_with(&public)),
+            );
+        }
+        files.sort();
+        files.dedup();
+
+        // binary and unreadable files can't refer to anything
+        let texts = files
+            .into_iter()
+            .filter_map(|f| fs::read_to_string(&f).ok().map(|text| (f, text)))
+            .collect();
+        Ok(ImageRefs { texts })
+    }
+
+    /// Files that refer to `file`, published in `dir`: by its path below `dir`,
+    /// such as `hello/cover.png` for `images/hello/cover.png`.
+    pub fn users(&self, dir: &Path, file: &Path) -> Vec<&Path> {
+        let name = images::slash_path(file.strip_prefix(dir).unwrap_or(file));
+        self.texts
+            .iter()
+            .filter(|(_, text)| images::references(text, &name))
+            .map(|(f, _)| f.as_path())
+            .collect()
+    }
+
+    /// Why `file`, published in `dir` with the post `post`, should stay when the
+    /// post is deleted: other files still refer to it, or, with `only_referenced`,
+    /// the post itself doesn't. None when it can go.
+    pub fn keep_reason(
+        &self,
+        dir: &Path,
+        file: &Path,
+        post: &Path,
+        only_referenced: bool,
+    ) -> Option<String> {
+        let users = self.users(dir, file);
+        let others: Vec<String> = users
+            .iter()
+            .filter(|u| **u != post)
+            .map(|u| u.display().to_string())
+            .collect();
+        if !others.is_empty() {
+            Some(format!("used by {}", others.join(", ")))
+        } else if only_referenced && !users.contains(&post) {
+            Some("not referenced by the post".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+/// `nuch images prune`: delete the images and assets in the collections'
+/// dirs that nothing refers to anymore, after a confirmation.
+pub fn run_prune(app_paths: &AppPaths, collection: Option<&str>) -> Result<()> {
+    if let Some(name) = collection
+        && !app_paths.collections.iter().any(|c| c.name == name)
+    {
+        return Err(anyhow::anyhow!("Collection '{}' not found", name));
+    }
+
+    let refs = ImageRefs::build(app_paths)?;
+    let mut seen: Vec<&PathBuf> = Vec::new();
+    // (site root, dir, unreferenced files in it)
+    let mut orphans: Vec<(PathBuf, &PathBuf, Vec<PathBuf>)> = Vec::new();
+    let mut checked = 0;
+    let mut total = 0;
+    for c in &app_paths.collections {
+        if collection.is_some_and(|name| c.name != name) {
+            continue;
+        }
+        for (dir, _) in c.asset_roots() {
+            if seen.contains(&dir) {
+                continue;
+            }
+            seen.push(dir);
+
+            let files = images::files_below(dir, &|p| {
+                app_paths
+                    .registry
+                    .kind_of(p)
+                    .is_some_and(|k| !k.is_content())
+            })?;
+            checked += files.len();
+            let unused: Vec<PathBuf> = files
+                .into_iter()
+                .filter(|f| refs.users(dir, f).is_empty())
+                .collect();
+            for f in &unused {
+                let size = fs::metadata(f).map(|m| m.len()).unwrap_or_default();
+                total += size;
+                println!("  {} ({})", f.display(), images::human_size(size));
+            }
+            if !unused.is_empty() {
+                orphans.push((git_helpers::get_site_root(&c.files), dir, unused));
+            }
+        }
+    }
+
+    let count: usize = orphans.iter().map(|(_, _, files)| files.len()).sum();
+    if count == 0 {
+        println!("{} file(s) checked, all referenced.", checked);
+        return Ok(());
+    }
+    let prompt = format!(
+        "Delete {} unreferenced file(s) ({}) and commit?",
+        count,
+        images::human_size(total)
+    );
+    if !Confirm::new(&prompt).with_default(false).prompt()? {
+        println!("Nothing deleted.");
+        return Ok(());
+    }
+
+    let mut sites: Vec<&PathBuf> = Vec::new();
+    for (site, _, _) in &orphans {
+        if !sites.contains(&site) {
+            sites.push(site);
+        }
+    }
+    for site_root in sites {
+        let in_site: Vec<(&PathBuf, &Vec<PathBuf>)> = orphans
+            .iter()
+            .filter(|(s, _, _)| s == site_root)
+            .map(|(_, dir, files)| (*dir, files))
+            .collect();
+        let paths: Vec<PathBuf> = in_site
+            .iter()
+            .flat_map(|(_, f)| f.iter().cloned())
+            .collect();
+        let (backup_dir, backups) = fs_helpers::backup_files_to_temp(&paths)?;
+        let removed = paths.iter().try_for_each(fs::remove_file);
+        let result = removed.map_err(anyhow::Error::from).and_then(|_| {
+            git_helpers::run_git_steps(site_root, "Prune unreferenced images", &paths)
+        });
+        if let Err(e) = result {
+            fs_helpers::restore_from_backups(&backups)?;
+            fs_helpers::cleanup_backup_dir(&backup_dir);
+            return Err(e);
+        }
+        fs_helpers::cleanup_backup_dir(&backup_dir);
+
+        // per-post folders left empty, but never the dirs themselves
+        for (dir, files) in in_site {
+            for f in files {
+                for parent in f.ancestors().skip(1) {
+                    if parent == dir.as_path() || fs::remove_dir(parent).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    println!("Deleted {} unreferenced file(s).", count);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn images_are_matched_by_their_path_in_the_dir() {
+        let refs = ImageRefs {
+            texts: vec![
+                (
+                    PathBuf::from("a.md"),
+                    "![c](/images/cover.png) ![h](/images/hello/shot.png)".to_string(),
+                ),
+                (
+                    PathBuf::from("b.vue"),
+                    "<img src=\"/images/logo.svg\">".to_string(),
+                ),
+            ],
+        };
+        let dir = Path::new("/site/public/images");
+        let users = |name: &str| refs.users(dir, &dir.join(name));
+        assert_eq!(users("cover.png"), [Path::new("a.md")]);
+        assert_eq!(users("hello/shot.png"), [Path::new("a.md")]);
+        assert_eq!(users("logo.svg"), [Path::new("b.vue")]);
+        // a name that also ends a longer path is kept rather than pruned
+        assert_eq!(users("shot.png"), [Path::new("a.md")]);
+        assert!(users("cover-1a2b3c4d.png").is_empty());
+        assert!(users("old/shot.png").is_empty());
+    }
+
+    #[test]
+    fn images_used_elsewhere_are_kept() {
+        let refs = ImageRefs {
+            texts: vec![
+                (
+                    PathBuf::from("a.md"),
+                    "![](/images/a.png) ![](/images/shared.png)".to_string(),
+                ),
+                (PathBuf::from("b.md"), "![](/images/shared.png)".to_string()),
+            ],
+        };
+        let dir = Path::new("/images");
+        let post = Path::new("a.md");
+        let keep = |name: &str, only| refs.keep_reason(dir, &dir.join(name), post, only);
+        assert_eq!(keep("a.png", false), None);
+        assert_eq!(keep("shared.png", false).as_deref(), Some("used by b.md"));
+        assert_eq!(keep("a-old.png", false), None);
+        assert_eq!(
+            keep("a-old.png", true).as_deref(),
+            Some("not referenced by the post")
+        );
+        assert_eq!(keep("a.png", true), None);
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{AppPaths, CollectionPaths};
+use crate::frontmatter;
+use crate::publish;
+
+/// A working file with a `publishAt` time.
+struct Scheduled {
+    file: PathBuf,
+    at: DateTime<Local>,
+    /// From `publishTo`, `--collection` or the only collection; None if unclear
+    collection: Option<CollectionPaths>,
+    published: bool,
+}
+
+/// `publishAt` as an RFC 3339 time (`2025-03-01T09:00:00+01:00`), or a local
+/// date and time (`2025-03-01 09:00`, `2025-03-01T09:00:00`, `2025-03-01`).
+fn parse_time(s: &str) -> Option<DateTime<Local>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t.with_timezone(&Local));
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+    })?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+/// Every working file with a `publishAt`, earliest first. Files with a time or
+/// collection nuch can't make sense of are reported and left out.
+fn scheduled(app_paths: &AppPaths, collection: Option<&str>) -> Result<Vec<Scheduled>> {
+    let fallback = match collection {
+        Some(name) => Some(
+            app_paths
+                .collections
+                .iter()
+                .find(|c| c.name == name)
+                .ok_or_else(|| anyhow::anyhow!("Collection '{}' not found", name))?,
+        ),
+        None if app_paths.collections.len() == 1 => app_paths.collections.first(),
+        None => None,
+    };
+
+    let mut out = Vec::new();
+    for file in app_paths.working_filter.list(&app_paths.working_files)? {
+        let Ok(src) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let Some(map) = frontmatter::split(&src).and_then(|fm| frontmatter::parse(fm.yaml).ok())
+        else {
+            continue;
+        };
+        let Some(value) = frontmatter::field_str(&map, "publishAt") else {
+            continue;
+        };
+        let Some(at) = parse_time(&value) else {
+            println!(
+                "Warning: {}: can't read publishAt '{}'",
+                file.display(),
+                value
+            );
+            continue;
+        };
+        let collection = match frontmatter::field_str(&map, "publishTo") {
+            Some(name) => match app_paths.collections.iter().find(|c| c.name == name) {
+                Some(c) => Some(c),
+                None => {
+                    println!(
+                        "Warning: {}: publishTo collection '{}' not found",
+                        file.display(),
+                        name
+                    );
+                    continue;
+                }
+            },
+            None => fallback,
+        };
+        let rel = file.strip_prefix(&app_paths.working_files).unwrap_or(&file);
+        let published = collection.is_some_and(|c| c.files.join(rel).exists());
+        out.push(Scheduled {
+            file,
+            at,
+            collection: collection.cloned(),
+            published,
+        });
+    }
+    out.sort_by_key(|s| s.at);
+    Ok(out)
+}
+
+/// `nuch schedule list`: every scheduled working file and what a run would do.
+pub fn run_list(app_paths: &AppPaths, collection: Option<&str>) -> Result<()> {
+    let queue = scheduled(app_paths, collection)?;
+    if queue.is_empty() {
+        println!("No working files have a publishAt time.");
+        return Ok(());
+    }
+    let now = Local::now();
+    for s in &queue {
+        let status = if s.published {
+            "published"
+        } else if s.collection.is_none() {
+            "no collection; set publishTo"
+        } else if s.at <= now {
+            "due"
+        } else {
+            "queued"
+        };
+        let rel = s
+            .file
+            .strip_prefix(&app_paths.working_files)
+            .unwrap_or(&s.file);
+        println!(
+            "{}  {:<12} {}  {}",
+            s.at.format("%Y-%m-%d %H:%M"),
+            s.collection.as_ref().map_or("-", |c| c.name.as_str()),
+            rel.display(),
+            status
+        );
+    }
+    Ok(())
+}
+
+/// `nuch schedule run`: publish every working file whose `publishAt` has
+/// passed and that isn't published yet, without asking. Safe to run from cron.
+pub fn run_due(app_paths: &AppPaths, collection: Option<&str>) -> Result<()> {
+    let now = Local::now();
+    let due: Vec<Scheduled> = scheduled(app_paths, collection)?
+        .into_iter()
+        .filter(|s| s.at <= now && !s.published)
+        .collect();
+    if due.is_empty() {
+        println!("Nothing due.");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for s in due {
+        let Some(c) = s.collection else {
+            println!(
+                "Skipping {}: several collections; set publishTo or pass --collection",
+                s.file.display()
+            );
+            failed += 1;
+            continue;
+        };
+        println!("Publishing {} to '{}'", s.file.display(), c.name);
+        if let Err(e) = publish::publish_selected(s.file.clone(), app_paths, c, false) {
+            println!("Failed to publish {}: {}", s.file.display(), e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "{} scheduled post(s) not published",
+            failed
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publish_times_are_read() {
+        let local = |s| {
+            Local
+                .from_local_datetime(&NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            parse_time("2025-03-01 09:00"),
+            Some(local("2025-03-01 09:00"))
+        );
+        assert_eq!(
+            parse_time("2025-03-01T09:00:00"),
+            Some(local("2025-03-01 09:00"))
+        );
+        assert_eq!(parse_time("2025-03-01"), Some(local("2025-03-01 00:00")));
+        assert_eq!(
+            parse_time("2025-03-01T09:00:00Z").map(|t| t.to_utc().to_rfc3339()),
+            Some("2025-03-01T09:00:00+00:00".to_string())
+        );
+        assert_eq!(parse_time("next tuesday"), None);
+    }
+}
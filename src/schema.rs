@@ -0,0 +1,356 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config;
+use crate::lint::Issue;
+
+/// A collection's `schema`: inline fields, or the path of a JSON Schema file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum SchemaConfig {
+    File(String),
+    Fields(BTreeMap<String, FieldSpec>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    #[default]
+    Any,
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Date,
+    List,
+    Object,
+}
+
+/// One front-matter field, e.g. `date = { type = "date", format = "%d/%m/%Y" }`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldSpec {
+    #[serde(rename = "type", default)]
+    pub ty: FieldType,
+    #[serde(default = "required_by_default")]
+    pub required: bool,
+    /// Allowed values
+    #[serde(rename = "enum", default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<String>,
+    /// chrono format of a `date` field (default: YYYY-MM-DD or an ISO 8601 date-time)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+fn required_by_default() -> bool {
+    true
+}
+
+/// The front-matter fields a collection expects.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    fields: BTreeMap<String, FieldSpec>,
+}
+
+impl Schema {
+    /// Load the schema configured for a collection; a file path is resolved like
+    /// the other config paths.
+    pub fn from_config(cfg: &SchemaConfig, relative_base: Option<&Path>) -> Result<Self> {
+        match cfg {
+            SchemaConfig::Fields(fields) => Ok(Self {
+                fields: fields.clone(),
+            }),
+            SchemaConfig::File(path) => {
+                let path = config::resolve_dir(path, relative_base)?;
+                let src = fs::read_to_string(&path)
+                    .map_err(|e| anyhow::anyhow!("cannot read {}: {}", path.display(), e))?;
+                Self::from_json_schema(&src)
+                    .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+            }
+        }
+    }
+
+    /// Read the `properties` and `required` of a JSON Schema object.
+    pub fn from_json_schema(src: &str) -> Result<Self> {
+        let root: serde_json::Value = serde_json::from_str(src)?;
+        let properties = root
+            .get("properties")
+            .and_then(|p| p.as_object())
+            .ok_or_else(|| anyhow::anyhow!("expected an object schema with \"properties\""))?;
+        let required: Vec<&str> = root
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|n| n.as_str()).collect())
+            .unwrap_or_default();
+
+        let fields = properties
+            .iter()
+            .map(|(name, prop)| {
+                // "type" may be a list such as ["string", "null"]
+                let ty = match prop.get("type") {
+                    Some(serde_json::Value::Array(types)) => types
+                        .iter()
+                        .filter_map(|t| t.as_str())
+                        .find(|t| *t != "null"),
+                    Some(t) => t.as_str(),
+                    None => None,
+                };
+                let format = prop.get("format").and_then(|f| f.as_str());
+                let ty = match (ty, format) {
+                    (Some("string"), Some("date" | "date-time")) => FieldType::Date,
+                    (Some("string"), _) => FieldType::String,
+                    (Some("number"), _) => FieldType::Number,
+                    (Some("integer"), _) => FieldType::Integer,
+                    (Some("boolean"), _) => FieldType::Boolean,
+                    (Some("array"), _) => FieldType::List,
+                    (Some("object"), _) => FieldType::Object,
+                    _ => FieldType::Any,
+                };
+                let allowed = prop
+                    .get("enum")
+                    .and_then(|e| e.as_array())
+                    .map(|e| {
+                        e.iter()
+                            .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let spec = FieldSpec {
+                    ty,
+                    required: required.contains(&name.as_str()),
+                    allowed,
+                    format: None,
+                };
+                (name.clone(), spec)
+            })
+            .collect();
+        Ok(Self { fields })
+    }
+
+    /// Mark fields as required, adding the ones the schema doesn't know.
+    pub fn require(&mut self, names: &[String]) {
+        for name in names {
+            self.fields
+                .entry(name.clone())
+                .and_modify(|f| f.required = true)
+                .or_insert(FieldSpec {
+                    ty: FieldType::Any,
+                    required: true,
+                    allowed: Vec::new(),
+                    format: None,
+                });
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Check `map`, parsed from `src`, whose first line is line `first_line` of the file.
+    pub fn check(&self, map: &Mapping, src: &str, first_line: usize) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        for (name, spec) in &self.fields {
+            let value = map.get(name.as_str()).filter(|v| !v.is_null());
+            let Some(value) = value else {
+                if spec.required {
+                    issues.push(Issue::new(
+                        None,
+                        format!("missing required field '{}'", name),
+                    ));
+                }
+                continue;
+            };
+            if spec.required && value.as_str() == Some("") {
+                issues.push(Issue::new(
+                    key_line(src, name).map(|l| l + first_line - 1),
+                    format!("required field '{}' is empty", name),
+                ));
+                continue;
+            }
+            if let Some(problem) = spec.problem_with(value) {
+                issues.push(Issue::new(
+                    key_line(src, name).map(|l| l + first_line - 1),
+                    format!("'{}' {}", name, problem),
+                ));
+            }
+        }
+        // in file order, missing fields last
+        issues.sort_by_key(|i| i.line.unwrap_or(usize::MAX));
+        issues
+    }
+}
+
+impl FieldSpec {
+    fn problem_with(&self, value: &Value) -> Option<String> {
+        let type_ok = match self.ty {
+            FieldType::Any => true,
+            FieldType::String => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Boolean => value.is_bool(),
+            FieldType::List => value.is_sequence(),
+            FieldType::Object => value.is_mapping(),
+            FieldType::Date => {
+                return match value.as_str() {
+                    Some(s) if is_date(s, self.format.as_deref()) => self.enum_problem(value),
+                    _ => Some(format!(
+                        "is not a valid date (expected {}), found {}",
+                        self.format.as_deref().unwrap_or("YYYY-MM-DD"),
+                        describe(value)
+                    )),
+                };
+            }
+        };
+        if !type_ok {
+            return Some(format!(
+                "should be {}, found {}",
+                type_name(self.ty),
+                describe(value)
+            ));
+        }
+        self.enum_problem(value)
+    }
+
+    fn enum_problem(&self, value: &Value) -> Option<String> {
+        if self.allowed.is_empty() {
+            return None;
+        }
+        let text = match value {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => return Some(format!("must be one of: {}", self.allowed.join(", "))),
+        };
+        if self.allowed.contains(&text) {
+            None
+        } else {
+            Some(format!(
+                "must be one of: {} (found '{}')",
+                self.allowed.join(", "),
+                text
+            ))
+        }
+    }
+}
+
+fn is_date(s: &str, format: Option<&str>) -> bool {
+    match format {
+        Some(f) => {
+            NaiveDate::parse_from_str(s, f).is_ok() || NaiveDateTime::parse_from_str(s, f).is_ok()
+        }
+        None => {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()
+                || DateTime::parse_from_rfc3339(s).is_ok()
+                || NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").is_ok()
+                || NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").is_ok()
+                || NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").is_ok()
+        }
+    }
+}
+
+fn type_name(ty: FieldType) -> &'static str {
+    match ty {
+        FieldType::Any => "any value",
+        FieldType::String => "a string",
+        FieldType::Number => "a number",
+        FieldType::Integer => "an integer",
+        FieldType::Boolean => "true or false",
+        FieldType::Date => "a date",
+        FieldType::List => "a list",
+        FieldType::Object => "a mapping",
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("'{}'", s),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Sequence(_) => "a list".to_string(),
+        Value::Mapping(_) => "a mapping".to_string(),
+        _ => "nothing".to_string(),
+    }
+}
+
+/// 1-based line of `key` in YAML or JSON source, preferring top-level keys.
+fn key_line(src: &str, key: &str) -> Option<usize> {
+    let prefixes = [
+        format!("{}:", key),
+        format!("\"{}\":", key),
+        format!("'{}':", key),
+        format!("\"{}\" :", key),
+    ];
+    let mut nested = None;
+    for (i, line) in src.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if prefixes.iter().any(|p| trimmed.starts_with(p.as_str())) {
+            if trimmed.len() == line.len() {
+                return Some(i + 1);
+            }
+            nested.get_or_insert(i + 1);
+        }
+    }
+    nested
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontmatter;
+
+    const TOML: &str = r#"
+title = { type = "string" }
+date = { type = "date" }
+description = { type = "string" }
+category = { type = "string", enum = ["news", "guide"], required = false }
+draft = { type = "boolean", required = false }
+"#;
+
+    fn check(schema: &Schema, src: &str) -> Vec<Issue> {
+        let fm = frontmatter::split(src).unwrap();
+        let map = frontmatter::parse(fm.yaml).unwrap();
+        schema.check(&map, fm.yaml, fm.first_line)
+    }
+
+    #[test]
+    fn inline_schema_reports_lines() {
+        let cfg: SchemaConfig = toml::from_str(TOML).unwrap();
+        let schema = Schema::from_config(&cfg, None).unwrap();
+
+        let ok = "---\ntitle: Hi\ndate: 2024-05-01\ndescription: About\n---\n";
+        assert!(check(&schema, ok).is_empty());
+
+        let bad = "---\ntitle: Hi\ndate: 05/01/2024\ncategory: misc\ndraft: maybe\n---\n";
+        let issues = check(&schema, bad);
+        let lines: Vec<_> = issues.iter().map(|i| i.line).collect();
+        assert_eq!(lines, [Some(3), Some(4), Some(5), None]);
+        assert!(issues[1].message.contains("news, guide"));
+        assert!(issues[2].message.contains("true or false"));
+        assert!(issues[3].message.contains("description"));
+    }
+
+    #[test]
+    fn json_schema_is_read() {
+        let src = r#"{
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "date": { "type": "string", "format": "date" },
+                "views": { "type": ["integer", "null"] }
+            },
+            "required": ["title", "date"]
+        }"#;
+        let schema = Schema::from_json_schema(src).unwrap();
+        assert_eq!(schema.fields["date"].ty, FieldType::Date);
+        assert_eq!(schema.fields["views"].ty, FieldType::Integer);
+        assert!(!schema.fields["views"].required);
+
+        let issues = check(&schema, "---\ndate: 2024-13-01\nviews: 1.5\n---\n");
+        assert_eq!(issues.len(), 3);
+        assert!(Schema::from_json_schema("[]").is_err());
+    }
+}
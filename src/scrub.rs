@@ -0,0 +1,291 @@
+use anyhow::Result;
+
+/// Remove EXIF, XMP, IPTC and text metadata from a JPEG, PNG or WebP file without
+/// re-encoding it. Other formats are returned unchanged. A JPEG's orientation
+/// is kept so photos don't end up sideways.
+pub fn scrub(bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        scrub_jpeg(bytes)
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        scrub_png(bytes)
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        scrub_webp(bytes)
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+fn truncated(what: &str) -> anyhow::Error {
+    anyhow::anyhow!("{} is truncated or malformed", what)
+}
+
+fn scrub_jpeg(bytes: &[u8]) -> Result<Vec<u8>> {
+    let segments = jpeg_segments(bytes)?;
+    let orientation = segments
+        .iter()
+        .filter(|(marker, _)| *marker == 0xE1)
+        .find_map(|(_, seg)| seg.get(4..)?.strip_prefix(b"Exif\0\0"))
+        .and_then(exif_orientation)
+        .filter(|&o| o != 1);
+
+    let mut out = vec![0xFF, 0xD8];
+    let mut pending = orientation.map(orientation_segment);
+    for (marker, seg) in segments {
+        let data = seg.get(4..).unwrap_or_default();
+        let keep = match marker {
+            // JFIF header
+            0xE0 => true,
+            // EXIF or XMP
+            0xE1 => false,
+            // ICC colour profile
+            0xE2 => data.starts_with(b"ICC_PROFILE\0"),
+            // Adobe colour transform, needed to decode some CMYK files
+            0xEE => data.starts_with(b"Adobe"),
+            // other application segments (IPTC, maker data) and comments
+            0xE3..=0xEF | 0xFE => false,
+            _ => true,
+        };
+        // EXIF goes right after SOI, or after the JFIF header that must come first
+        if marker != 0xE0
+            && let Some(exif) = pending.take()
+        {
+            out.extend_from_slice(&exif);
+        }
+        if keep {
+            out.extend_from_slice(seg);
+        }
+    }
+    Ok(out)
+}
+
+/// Marker and bytes of each segment after SOI. Start of scan is returned together
+/// with everything after it, the compressed image data.
+fn jpeg_segments(bytes: &[u8]) -> Result<Vec<(u8, &[u8])>> {
+    let mut segments = Vec::new();
+    let mut pos = 2;
+    loop {
+        // markers may be padded with extra 0xFF bytes
+        while bytes.get(pos) == Some(&0xFF) && bytes.get(pos + 1) == Some(&0xFF) {
+            pos += 1;
+        }
+        let (&ff, &marker) = bytes
+            .get(pos)
+            .zip(bytes.get(pos + 1))
+            .ok_or_else(|| truncated("JPEG"))?;
+        if ff != 0xFF {
+            return Err(truncated("JPEG"));
+        }
+        // markers without a length
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            segments.push((marker, &bytes[pos..pos + 2]));
+            pos += 2;
+            continue;
+        }
+        if marker == 0xD9 || marker == 0xDA {
+            segments.push((marker, &bytes[pos..]));
+            return Ok(segments);
+        }
+
+        let len = bytes
+            .get(pos + 2..pos + 4)
+            .map(|l| u16::from_be_bytes([l[0], l[1]]) as usize)
+            .ok_or_else(|| truncated("JPEG"))?;
+        let end = pos + 2 + len;
+        segments.push((
+            marker,
+            bytes.get(pos..end).ok_or_else(|| truncated("JPEG"))?,
+        ));
+        pos = end;
+    }
+}
+
+/// The orientation tag (0x0112) in IFD0 of an EXIF TIFF block.
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |i: usize| -> Option<u16> {
+        let b = tiff.get(i..i + 2)?;
+        Some(if big_endian {
+            u16::from_be_bytes([b[0], b[1]])
+        } else {
+            u16::from_le_bytes([b[0], b[1]])
+        })
+    };
+    let u32_at = |i: usize| -> Option<u32> {
+        let b = tiff.get(i..i + 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    };
+
+    let ifd = u32_at(4)? as usize;
+    let count = u16_at(ifd)? as usize;
+    (0..count)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(0x0112))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+/// An APP1 segment holding nothing but the orientation tag.
+fn orientation_segment(orientation: u16) -> Vec<u8> {
+    let mut tiff = Vec::new();
+    tiff.extend_from_slice(b"MM\0\x2a");
+    tiff.extend_from_slice(&8u32.to_be_bytes());
+    tiff.extend_from_slice(&1u16.to_be_bytes());
+    // tag, type SHORT, count 1, value padded to four bytes
+    tiff.extend_from_slice(&0x0112u16.to_be_bytes());
+    tiff.extend_from_slice(&3u16.to_be_bytes());
+    tiff.extend_from_slice(&1u32.to_be_bytes());
+    tiff.extend_from_slice(&orientation.to_be_bytes());
+    tiff.extend_from_slice(&[0, 0]);
+    // no next IFD
+    tiff.extend_from_slice(&0u32.to_be_bytes());
+
+    let len = (2 + 6 + tiff.len()) as u16;
+    let mut seg = vec![0xFF, 0xE1];
+    seg.extend_from_slice(&len.to_be_bytes());
+    seg.extend_from_slice(b"Exif\0\0");
+    seg.extend_from_slice(&tiff);
+    seg
+}
+
+fn scrub_png(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut out = bytes[..8].to_vec();
+    let mut pos = 8;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or_else(|| truncated("PNG"))?;
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = &header[4..8];
+        // length, type, data, CRC
+        let end = pos + 12 + len;
+        let chunk = bytes.get(pos..end).ok_or_else(|| truncated("PNG"))?;
+        if !matches!(kind, b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME") {
+            out.extend_from_slice(chunk);
+        }
+        pos = end;
+        if kind == b"IEND" {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+fn scrub_webp(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut body = b"WEBP".to_vec();
+    let mut pos = 12;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or_else(|| truncated("WebP"))?;
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        // chunks are padded to an even size
+        let end = (pos + 8 + len + (len & 1)).min(bytes.len());
+        let chunk = bytes.get(pos..end).ok_or_else(|| truncated("WebP"))?;
+        match &header[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" if chunk.len() > 8 => {
+                let mut chunk = chunk.to_vec();
+                // clear the EXIF (0x08) and XMP (0x04) flags
+                chunk[8] &= !0x0C;
+                body.extend_from_slice(&chunk);
+            }
+            _ => body.extend_from_slice(chunk),
+        }
+        pos = end;
+    }
+    let mut out = b"RIFF".to_vec();
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    fn encoded(format: ImageFormat) -> Vec<u8> {
+        let mut out = Cursor::new(Vec::new());
+        RgbImage::from_pixel(8, 8, image::Rgb([10, 20, 30]))
+            .write_to(&mut out, format)
+            .unwrap();
+        out.into_inner()
+    }
+
+    fn exif_with_gps(orientation: u16) -> Vec<u8> {
+        // little-endian TIFF: orientation and a GPS IFD pointer in IFD0
+        let mut tiff = b"II\x2a\0".to_vec();
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0]);
+        tiff.extend_from_slice(&orientation.to_le_bytes());
+        tiff.extend_from_slice(&[0, 0]);
+        tiff.extend_from_slice(&[0x25, 0x88, 4, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(b"GPS 51.5N 0.1W");
+
+        let mut seg = vec![0xFF, 0xE1];
+        seg.extend_from_slice(&((2 + 6 + tiff.len()) as u16).to_be_bytes());
+        seg.extend_from_slice(b"Exif\0\0");
+        seg.extend_from_slice(&tiff);
+        seg
+    }
+
+    #[test]
+    fn jpeg_keeps_only_orientation() {
+        let jpeg = encoded(ImageFormat::Jpeg);
+        let mut dirty = jpeg[..2].to_vec();
+        dirty.extend_from_slice(&exif_with_gps(6));
+        dirty.extend_from_slice(&[0xFF, 0xFE, 0, 7, b's', b'e', b'r', b'i', b'a']);
+        dirty.extend_from_slice(&jpeg[2..]);
+
+        let clean = scrub(&dirty).unwrap();
+        assert!(!clean.windows(3).any(|w| w == b"GPS"));
+        assert!(!clean.windows(5).any(|w| w == b"seria"));
+        assert!(clean.len() < dirty.len());
+        assert_eq!(exif_orientation(&orientation_segment(6)[10..]), Some(6));
+        assert!(clean.windows(6).any(|w| w == b"Exif\0\0"));
+        image::load_from_memory(&clean).unwrap();
+
+        // upright photos get no EXIF at all
+        let mut upright = jpeg[..2].to_vec();
+        upright.extend_from_slice(&exif_with_gps(1));
+        upright.extend_from_slice(&jpeg[2..]);
+        assert_eq!(scrub(&upright).unwrap(), jpeg);
+    }
+
+    #[test]
+    fn png_and_webp_chunks_are_dropped() {
+        let png = encoded(ImageFormat::Png);
+        let iend = png.len() - 12;
+        let mut dirty = png[..iend].to_vec();
+        dirty.extend_from_slice(&[0, 0, 0, 3]);
+        dirty.extend_from_slice(b"tEXtGPS");
+        dirty.extend_from_slice(&[0, 0, 0, 0]);
+        dirty.extend_from_slice(&png[iend..]);
+        assert_eq!(scrub(&dirty).unwrap(), png);
+
+        let mut body = b"WEBP".to_vec();
+        body.extend_from_slice(b"VP8X");
+        body.extend_from_slice(&10u32.to_le_bytes());
+        body.extend_from_slice(&[0x0C, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        body.extend_from_slice(b"EXIF");
+        body.extend_from_slice(&3u32.to_le_bytes());
+        body.extend_from_slice(b"GPS\0");
+        let mut webp = b"RIFF".to_vec();
+        webp.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        webp.extend_from_slice(&body);
+
+        let clean = scrub(&webp).unwrap();
+        assert_eq!(clean.len(), 12 + 18);
+        assert_eq!(u32::from_le_bytes(clean[4..8].try_into().unwrap()), 22);
+        assert_eq!(clean[20], 0);
+
+        assert_eq!(scrub(b"<svg/>").unwrap(), b"<svg/>");
+    }
+}
@@ -0,0 +1,564 @@
+use anyhow::Result;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap},
+};
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::config::{AppPaths, CollectionPaths};
+use crate::content::Registry;
+use crate::git as git_helpers;
+use crate::images;
+use crate::publish;
+
+const LOG_LINES: usize = 200;
+
+/// How a file in one pane relates to its counterpart in the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Marker {
+    /// Working file not published in this collection
+    Draft,
+    /// Published and identical to the working copy
+    Published,
+    /// Published, but working and published copies differ
+    Changed,
+    /// Published file without a working copy
+    NoDraft,
+}
+
+impl Marker {
+    fn symbol(self) -> &'static str {
+        match self {
+            Marker::Draft => "○",
+            Marker::Published => "●",
+            Marker::Changed => "◐",
+            Marker::NoDraft => "◌",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Marker::Draft => Color::Gray,
+            Marker::Published => Color::Green,
+            Marker::Changed => Color::Yellow,
+            Marker::NoDraft => Color::Blue,
+        }
+    }
+}
+
+fn marker_for(working: &Path, published: &Path, registry: &Registry) -> Marker {
+    match (working.exists(), published.exists()) {
+        (_, false) => Marker::Draft,
+        (false, true) => Marker::NoDraft,
+        (true, true) if publish::same_file(working, published, registry) => Marker::Published,
+        (true, true) => Marker::Changed,
+    }
+}
+
+struct Entry {
+    path: PathBuf,
+    rel: PathBuf,
+    marker: Marker,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Working,
+    Collection,
+}
+
+enum Action {
+    Publish(PathBuf),
+    Update(PathBuf),
+    Delete(PathBuf),
+}
+
+struct Popup {
+    title: String,
+    text: String,
+    scroll: u16,
+}
+
+struct App<'a> {
+    app_paths: &'a AppPaths,
+    collection: usize,
+    focus: Pane,
+    working: Vec<Entry>,
+    working_state: ListState,
+    published: Vec<Entry>,
+    published_state: ListState,
+    log: Vec<String>,
+    popup: Option<Popup>,
+    quit: bool,
+}
+
+/// Two-pane browser for the working dir and one collection at a time.
+pub fn run_tui(app_paths: &AppPaths) -> Result<()> {
+    if app_paths.collections.is_empty() {
+        return Err(anyhow::anyhow!("No collections configured."));
+    }
+
+    let mut app = App::new(app_paths);
+    app.reload()?;
+
+    let mut terminal = match ratatui::try_init() {
+        Ok(t) => t,
+        Err(e) => {
+            ratatui::restore();
+            return Err(anyhow::anyhow!("Can't open nuch tui: {}", e));
+        }
+    };
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    while !app.quit {
+        terminal.draw(|f| app.draw(f))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if let Some(action) = app.handle_key(key)? {
+            // the publish/delete flows print and prompt, so hand them a normal terminal
+            ratatui::restore();
+            let outcome = app.run_action(action);
+            println!("\nPress Enter to return to nuch tui");
+            let _ = std::io::stdin().lock().read_line(&mut String::new());
+            *terminal = ratatui::try_init().inspect_err(|_| ratatui::restore())?;
+
+            app.record(outcome);
+            app.reload()?;
+        }
+    }
+    Ok(())
+}
+
+impl<'a> App<'a> {
+    fn new(app_paths: &'a AppPaths) -> Self {
+        Self {
+            app_paths,
+            collection: 0,
+            focus: Pane::Working,
+            working: Vec::new(),
+            working_state: ListState::default(),
+            published: Vec::new(),
+            published_state: ListState::default(),
+            log: vec!["Ready.".to_string()],
+            popup: None,
+            quit: false,
+        }
+    }
+
+    fn current(&self) -> &'a CollectionPaths {
+        &self.app_paths.collections[self.collection]
+    }
+
+    /// Re-read both panes, keeping the selections where they were.
+    fn reload(&mut self) -> Result<()> {
+        let col = self.current();
+        let working_root = &self.app_paths.working_files;
+        let registry = &self.app_paths.registry;
+
+        self.working = self
+            .app_paths
+            .working_filter
+            .list(working_root)?
+            .into_iter()
+            .filter_map(|path| {
+                let rel = path.strip_prefix(working_root).ok()?.to_path_buf();
+                if !col.filter.matches(&rel) {
+                    return None;
+                }
+                let marker = marker_for(&path, &col.files.join(&rel), registry);
+                Some(Entry { path, rel, marker })
+            })
+            .collect();
+
+        self.published = col
+            .filter
+            .list(&col.files)?
+            .into_iter()
+            .filter_map(|path| {
+                let rel = path.strip_prefix(&col.files).ok()?.to_path_buf();
+                let marker = marker_for(&working_root.join(&rel), &path, registry);
+                Some(Entry { path, rel, marker })
+            })
+            .collect();
+
+        clamp(&mut self.working_state, self.working.len());
+        clamp(&mut self.published_state, self.published.len());
+        Ok(())
+    }
+
+    fn selected(&self) -> Option<&Entry> {
+        match self.focus {
+            Pane::Working => self
+                .working_state
+                .selected()
+                .and_then(|i| self.working.get(i)),
+            Pane::Collection => self
+                .published_state
+                .selected()
+                .and_then(|i| self.published.get(i)),
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if let Some(popup) = &mut self.popup {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => self.popup = None,
+                KeyCode::Down | KeyCode::Char('j') => popup.scroll = popup.scroll.saturating_add(1),
+                KeyCode::Up | KeyCode::Char('k') => popup.scroll = popup.scroll.saturating_sub(1),
+                KeyCode::PageDown => popup.scroll = popup.scroll.saturating_add(20),
+                KeyCode::PageUp => popup.scroll = popup.scroll.saturating_sub(20),
+                _ => {}
+            }
+            return Ok(None);
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Pane::Working => Pane::Collection,
+                    Pane::Collection => Pane::Working,
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left => self.focus = Pane::Working,
+            KeyCode::Char('l') | KeyCode::Right => self.focus = Pane::Collection,
+            KeyCode::Down | KeyCode::Char('j') => self.state_mut().select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.state_mut().select_previous(),
+            KeyCode::Char(']') => {
+                self.collection = (self.collection + 1) % self.app_paths.collections.len();
+                self.reload()?;
+            }
+            KeyCode::Char('[') => {
+                let n = self.app_paths.collections.len();
+                self.collection = (self.collection + n - 1) % n;
+                self.reload()?;
+            }
+            KeyCode::Char('r') => {
+                self.reload()?;
+                self.log("Refreshed.");
+            }
+            KeyCode::Char('v') | KeyCode::Enter => self.show_preview(),
+            KeyCode::Char('D') => self.show_diff(),
+            KeyCode::Char('p') => return Ok(self.publish_action()),
+            KeyCode::Char('u') => return Ok(self.update_action()),
+            KeyCode::Char('d') => return Ok(self.delete_action()),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn state_mut(&mut self) -> &mut ListState {
+        match self.focus {
+            Pane::Working => &mut self.working_state,
+            Pane::Collection => &mut self.published_state,
+        }
+    }
+
+    fn publish_action(&mut self) -> Option<Action> {
+        if self.focus != Pane::Working {
+            self.log("Select a file in the working pane to publish.");
+            return None;
+        }
+        let entry = self.selected()?;
+        if entry.marker != Marker::Draft {
+            let msg = format!(
+                "{} is already published; press u to update it.",
+                entry.rel.display()
+            );
+            self.log(&msg);
+            return None;
+        }
+        Some(Action::Publish(entry.path.clone()))
+    }
+
+    fn update_action(&mut self) -> Option<Action> {
+        let entry = self.selected()?;
+        let working = self.app_paths.working_files.join(&entry.rel);
+        let msg = match entry.marker {
+            Marker::Changed => return Some(Action::Update(working)),
+            Marker::Published => format!("{} is already up to date.", entry.rel.display()),
+            Marker::Draft => format!("{} is not published; press p.", entry.rel.display()),
+            Marker::NoDraft => format!("{} has no working copy.", entry.rel.display()),
+        };
+        self.log(&msg);
+        None
+    }
+
+    fn delete_action(&mut self) -> Option<Action> {
+        if self.focus != Pane::Collection {
+            self.log("Select a file in the collection pane to delete.");
+            return None;
+        }
+        self.selected().map(|e| Action::Delete(e.path.clone()))
+    }
+
+    fn run_action(&self, action: Action) -> (String, Result<()>) {
+        let col = self.current().clone();
+        // only this action's git output belongs in the log
+        git_helpers::take_output();
+        match action {
+            Action::Publish(p) => (
+                format!("publish {}", p.display()),
+                publish::publish_selected(p, self.app_paths, col, true),
+            ),
+            Action::Update(p) => (
+                format!("update {}", p.display()),
+                publish::update_selected(p, self.app_paths, col),
+            ),
+            Action::Delete(p) => (
+                format!("delete {}", p.display()),
+                publish::delete_selected(p, col, self.app_paths, false, false),
+            ),
+        }
+    }
+
+    /// Log the outcome of an action and what git printed while it ran.
+    fn record(&mut self, (what, outcome): (String, Result<()>)) {
+        let output = git_helpers::take_output();
+        match &outcome {
+            Ok(()) if output.is_empty() => self.log(&format!("{}: done, nothing committed", what)),
+            Ok(()) => self.log(&format!("{}: done", what)),
+            Err(_) => self.log(&format!("{}: failed", what)),
+        }
+        for line in &output {
+            self.log(&format!("  {}", line));
+        }
+        if let Err(e) = outcome {
+            for line in e.to_string().lines().filter(|l| !l.trim().is_empty()) {
+                self.log(&format!("  error: {}", line));
+            }
+        }
+    }
+
+    fn log(&mut self, line: &str) {
+        self.log.push(line.to_string());
+        if self.log.len() > LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    fn show_preview(&mut self) {
+        let Some(entry) = self.selected() else {
+            return;
+        };
+        let mut text = fs::read_to_string(&entry.path)
+            .unwrap_or_else(|e| format!("(cannot read {}: {})", entry.path.display(), e));
+
+        let stem = entry
+            .path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let collection = match self.focus {
+            Pane::Working => None,
+            Pane::Collection => Some(self.current()),
+        };
+        if let Some(files) = images::preview_assets(self.app_paths, collection, stem) {
+            text.push_str("\n── images ──\n");
+            if files.is_empty() {
+                text.push_str("none\n");
+            }
+            for f in files {
+                text.push_str(&format!("{}\n", f.display()));
+            }
+        }
+
+        self.popup = Some(Popup {
+            title: entry.rel.display().to_string(),
+            text,
+            scroll: 0,
+        });
+    }
+
+    fn show_diff(&mut self) {
+        let Some(entry) = self.selected() else {
+            return;
+        };
+        let working = self.app_paths.working_files.join(&entry.rel);
+        let published = self.current().files.join(&entry.rel);
+        if !working.exists() || !published.exists() {
+            let msg = format!(
+                "{}: needs both a working and a published copy to diff.",
+                entry.rel.display()
+            );
+            self.log(&msg);
+            return;
+        }
+        let title = format!("diff {}", entry.rel.display());
+        match git_helpers::diff_files(&published, &working) {
+            Ok(diff) if diff.is_empty() => self.log(&format!("{}: no changes", title)),
+            Ok(text) => {
+                self.popup = Some(Popup {
+                    title,
+                    text,
+                    scroll: 0,
+                })
+            }
+            Err(e) => self.log(&format!("{}: {}", title, e)),
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let [panes, log_area, help_area] = Layout::vertical([
+            Constraint::Min(5),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(f.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(panes);
+
+        let col = self.current();
+        let working_title = format!(" working: {} ", self.app_paths.working_files.display());
+        let col_title = format!(
+            " collection {}/{}: {} ",
+            self.collection + 1,
+            self.app_paths.collections.len(),
+            col.name
+        );
+        let working_list = pane_list(&self.working, working_title, self.focus == Pane::Working);
+        let published_list = pane_list(&self.published, col_title, self.focus == Pane::Collection);
+        f.render_stateful_widget(working_list, left, &mut self.working_state);
+        f.render_stateful_widget(published_list, right, &mut self.published_state);
+
+        let visible = log_area.height.saturating_sub(2) as usize;
+        let log_lines: Vec<Line> = self
+            .log
+            .iter()
+            .skip(self.log.len().saturating_sub(visible))
+            .map(|l| Line::from(l.as_str()))
+            .collect();
+        f.render_widget(
+            Paragraph::new(log_lines).block(Block::bordered().title(" log ")),
+            log_area,
+        );
+
+        f.render_widget(
+            Paragraph::new(
+                "tab/h/l switch · j/k move · [ ] collection · p publish · u update · d delete · D diff · v preview · r refresh · q quit",
+            )
+            .style(Style::default().fg(Color::DarkGray)),
+            help_area,
+        );
+
+        if let Some(popup) = &self.popup {
+            let area = centered(f.area(), 85, 85);
+            f.render_widget(Clear, area);
+            f.render_widget(
+                Paragraph::new(popup.text.as_str())
+                    .block(Block::bordered().title(format!(" {} (esc to close) ", popup.title)))
+                    .wrap(Wrap { trim: false })
+                    .scroll((popup.scroll, 0)),
+                area,
+            );
+        }
+    }
+}
+
+fn pane_list(entries: &[Entry], title: String, focused: bool) -> List<'static> {
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|e| {
+            ListItem::new(Line::from(vec![
+                ratatui::text::Span::styled(
+                    e.marker.symbol(),
+                    Style::default().fg(e.marker.color()),
+                ),
+                ratatui::text::Span::raw(format!(" {}", e.rel.display())),
+            ]))
+        })
+        .collect();
+    let border = if focused {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    };
+    List::new(items)
+        .block(Block::bordered().title(title).border_style(border))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+}
+
+fn clamp(state: &mut ListState, len: usize) {
+    match (state.selected(), len) {
+        (_, 0) => state.select(None),
+        (None, _) => state.select(Some(0)),
+        (Some(i), n) if i >= n => state.select(Some(n - 1)),
+        _ => {}
+    }
+}
+
+fn centered(area: Rect, pct_x: u16, pct_y: u16) -> Rect {
+    let w = area.width * pct_x / 100;
+    let h = area.height * pct_y / 100;
+    Rect {
+        x: area.x + (area.width - w) / 2,
+        y: area.y + (area.height - h) / 2,
+        width: w,
+        height: h,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn marker_compares_working_and_published() {
+        let td = tempdir().unwrap();
+        let registry = Registry::default();
+        let working = td.path().join("w.md");
+        let published = td.path().join("p.md");
+
+        std::fs::write(&working, "a").unwrap();
+        assert_eq!(marker_for(&working, &published, &registry), Marker::Draft);
+
+        std::fs::write(&published, "a").unwrap();
+        assert_eq!(
+            marker_for(&working, &published, &registry),
+            Marker::Published
+        );
+
+        std::fs::write(&published, "b").unwrap();
+        assert_eq!(marker_for(&working, &published, &registry), Marker::Changed);
+
+        // publishing renames and moves images, which isn't a change
+        std::fs::write(&working, "![a](/images/hi-cover.png)\n").unwrap();
+        std::fs::write(&published, "![a](/images/hi/hi-cover-1a2b3c4d.webp)\n").unwrap();
+        assert_eq!(
+            marker_for(&working, &published, &registry),
+            Marker::Published
+        );
+
+        std::fs::remove_file(&working).unwrap();
+        assert_eq!(marker_for(&working, &published, &registry), Marker::NoDraft);
+    }
+
+    #[test]
+    fn clamp_keeps_selection_in_range() {
+        let mut state = ListState::default();
+        clamp(&mut state, 3);
+        assert_eq!(state.selected(), Some(0));
+        state.select(Some(5));
+        clamp(&mut state, 3);
+        assert_eq!(state.selected(), Some(2));
+        clamp(&mut state, 0);
+        assert_eq!(state.selected(), None);
+    }
+}
@@ -1,34 +1,77 @@
 use anyhow::Result;
 use inquire::Select;
-use std::{
-    fs::read_dir,
-    path::{Path, PathBuf},
+use ratatui::{
+    DefaultTerminal,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
 };
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use crate::config::CollectionPaths;
+use crate::config::{AppPaths, CollectionPaths, GroupBy, PickerConfig, SortBy};
+use crate::content::Kind;
+use crate::frontmatter;
+use crate::fs::FileFilter;
+use crate::images;
+use crate::publish;
 
-pub fn list_blogs(dir: &Path, exclude_dir: Option<&CollectionPaths>) -> Result<Option<PathBuf>> {
-    let mut content_files: Vec<_> = Vec::new();
-    let supported_exts = ["md", "yaml", "yml", "json", "csv"];
+/// Lines of a file shown in the picker's preview.
+const PREVIEW_LINES: usize = 8;
+
+/// One entry in the file picker: the file and its formatted columns.
+struct FileRow {
+    path: PathBuf,
+    label: String,
+}
 
-    if dir.is_dir() {
-        for entry in read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
+impl fmt::Display for FileRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
 
-            let is_supported = path.is_file()
-                && path
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .is_some_and(|ext| supported_exts.contains(&ext));
+/// What the picker shows about a file besides its name.
+#[derive(Debug, Default, PartialEq)]
+struct FileInfo {
+    title: Option<String>,
+    date: Option<String>,
+    words: Option<usize>,
+    tags: Vec<String>,
+}
 
-            let is_excluded = exclude_dir
-                .map(|ex| ex.files.join(path.file_name().unwrap()).exists())
-                .unwrap_or(false);
+/// A listed file with everything the picker sorts and groups by.
+struct Candidate {
+    path: PathBuf,
+    name: String,
+    info: FileInfo,
+    modified: Option<SystemTime>,
+}
 
-            if is_supported && !is_excluded {
-                content_files.push(path);
-            }
+/// List the content files under `dir` accepted by `filter` and let the user pick one.
+/// When publishing, `target` is the destination collection: files it doesn't accept,
+/// or that are already published there, are left out.
+pub fn list_blogs(
+    dir: &Path,
+    filter: &FileFilter,
+    target: Option<&CollectionPaths>,
+    app_paths: &AppPaths,
+) -> Result<Option<PathBuf>> {
+    let mut content_files: Vec<_> = Vec::new();
+
+    for path in filter.list(dir)? {
+        let rel = path.strip_prefix(dir).unwrap_or(&path);
+
+        let is_excluded = target
+            .map(|t| !t.filter.matches(rel) || t.files.join(rel).exists())
+            .unwrap_or(false);
+
+        if !is_excluded {
+            content_files.push(path);
         }
     }
 
@@ -36,43 +79,435 @@ pub fn list_blogs(dir: &Path, exclude_dir: Option<&CollectionPaths>) -> Result<O
         println!("No supported files found.");
         return Ok(None);
     }
-    content_files.sort();
+    let rows = file_rows(content_files, dir, app_paths);
+    let picked = pick(&rows, |path| preview(path, dir, app_paths))?;
+    match picked {
+        Some(i) => {
+            let path = &rows[i].path;
+            println!(
+                "Selected {}",
+                path.strip_prefix(dir).unwrap_or(path).display()
+            );
+            Ok(Some(path.clone()))
+        }
+        None => {
+            println!("Cancelled.");
+            Ok(None)
+        }
+    }
+}
+
+/// Full-screen picker: the rows, filtered by what's typed, above a preview of
+/// the row under the cursor. Returns the index of the chosen row.
+fn pick(rows: &[FileRow], preview: impl Fn(&Path) -> String) -> Result<Option<usize>> {
+    let mut terminal = match ratatui::try_init() {
+        Ok(t) => t,
+        Err(e) => {
+            ratatui::restore();
+            return Err(anyhow::anyhow!("Can't open the file picker: {}", e));
+        }
+    };
+    let result = pick_loop(&mut terminal, rows, &preview);
+    ratatui::restore();
+    result
+}
+
+fn pick_loop(
+    terminal: &mut DefaultTerminal,
+    rows: &[FileRow],
+    preview: &impl Fn(&Path) -> String,
+) -> Result<Option<usize>> {
+    let mut query = String::new();
+    let mut shown: Vec<usize> = (0..rows.len()).collect();
+    let mut state = ListState::default().with_selected(Some(0));
+    // the preview of the row under the cursor, read again only when it moves
+    let mut previewed: Option<(usize, String)> = None;
+    let mut page = 1;
+
+    loop {
+        let current = state.selected().and_then(|i| shown.get(i).copied());
+        if let Some(i) = current
+            && previewed.as_ref().is_none_or(|(p, _)| *p != i)
+        {
+            previewed = Some((i, preview(&rows[i].path)));
+        }
+
+        terminal.draw(|f| {
+            let [prompt_area, list_area, preview_area, help_area] = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Min(3),
+                Constraint::Length(PREVIEW_LINES as u16 + 3),
+                Constraint::Length(1),
+            ])
+            .areas(f.area());
+            page = list_area.height.saturating_sub(2).max(1) as usize;
+
+            f.render_widget(
+                Paragraph::new(format!("Select a file: {}", query)),
+                prompt_area,
+            );
+            let items: Vec<ListItem> = shown
+                .iter()
+                .map(|&i| ListItem::new(rows[i].label.as_str()))
+                .collect();
+            let title = format!(" {}/{} ", shown.len(), rows.len());
+            f.render_stateful_widget(
+                List::new(items)
+                    .block(Block::bordered().title(title))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+                list_area,
+                &mut state,
+            );
+            let (title, text) = match (current, &previewed) {
+                (Some(i), Some((_, text))) => {
+                    (format!(" {} ", rows[i].path.display()), text.as_str())
+                }
+                _ => (" no match ".to_string(), ""),
+            };
+            f.render_widget(
+                Paragraph::new(text).block(Block::bordered().title(title)),
+                preview_area,
+            );
+            f.render_widget(
+                Paragraph::new("type to filter, ↑↓ to move, enter to select, esc to quit")
+                    .style(Style::default().fg(Color::DarkGray)),
+                help_area,
+            );
+        })?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let last = shown.len().saturating_sub(1);
+        let at = state.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(None);
+            }
+            KeyCode::Enter if current.is_some() => return Ok(current),
+            KeyCode::Up => state.select(Some(if at == 0 { last } else { at - 1 })),
+            KeyCode::Down => state.select(Some(if at >= last { 0 } else { at + 1 })),
+            KeyCode::PageUp => state.select(Some(at.saturating_sub(page))),
+            KeyCode::PageDown => state.select(Some((at + page).min(last))),
+            KeyCode::Home => state.select(Some(0)),
+            KeyCode::End => state.select(Some(last)),
+            KeyCode::Backspace | KeyCode::Char(_) => {
+                match key.code {
+                    KeyCode::Char(c) => query.push(c),
+                    _ => {
+                        query.pop();
+                    }
+                }
+                shown = ranked(rows.iter().map(|r| r.label.as_str()), &query);
+                state.select(Some(0));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Indexes of the labels `query` matches, best match first; equal scores keep
+/// the order they came in.
+fn ranked<'a>(labels: impl Iterator<Item = &'a str>, query: &str) -> Vec<usize> {
+    let mut scored: Vec<(i32, usize)> = labels
+        .enumerate()
+        .filter_map(|(i, label)| Some((fuzzy_score(label, query)?, i)))
+        .collect();
+    scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// How well `query` matches `label`, or None when its characters don't all
+/// appear in order (ignoring case and spaces). Letters that follow each other
+/// and letters starting a word (`hw` in `hello-world`) score higher, gaps lower.
+fn fuzzy_score(label: &str, query: &str) -> Option<i32> {
+    const MATCH: i32 = 1;
+    const RUN: i32 = 4;
+    const WORD_START: i32 = 6;
+    const GAP: i32 = 2;
+
+    let label: Vec<char> = label.chars().collect();
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let word_start = |i: usize| {
+        i == 0
+            || matches!(label[i - 1], '/' | '-' | '_' | ' ' | '.')
+            || (label[i - 1].is_lowercase() && label[i].is_uppercase())
+    };
 
-    let names: Vec<_> = content_files
+    // best[i]: best score with the query so far matched, its last letter at label[i]
+    let mut best: Vec<Option<i32>> = label
         .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            (lower(c) == query[0]).then(|| MATCH + if word_start(i) { WORD_START } else { 0 })
+        })
+        .collect();
+    for &q in &query[1..] {
+        let mut next = vec![None; label.len()];
+        // best score ending before i - 1, for matches after a gap
+        let mut before: Option<i32> = None;
+        for i in 1..label.len() {
+            if lower(label[i]) == q {
+                let bonus = MATCH + if word_start(i) { WORD_START } else { 0 };
+                let run = best[i - 1].map(|s| s + RUN);
+                let gap = before.map(|s| s - GAP);
+                next[i] = run.max(gap).map(|s| s + bonus);
+            }
+            before = before.max(best[i - 1]);
+        }
+        best = next;
+    }
+    best.into_iter().flatten().max()
+}
+
+fn file_rows(files: Vec<PathBuf>, dir: &Path, app_paths: &AppPaths) -> Vec<FileRow> {
+    let candidates: Vec<Candidate> = files
+        .into_iter()
         .map(|p| {
-            p.file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or_default()
-                .to_string()
+            let rel = p.strip_prefix(dir).unwrap_or(&p);
+            let info = fs::read_to_string(&p)
+                .map(|src| file_info(&src, app_paths.registry.kind_of(&p)))
+                .unwrap_or_default();
+            let modified = fs::metadata(&p).and_then(|m| m.modified()).ok();
+            Candidate {
+                name: rel.to_string_lossy().to_string(),
+                path: p,
+                info,
+                modified,
+            }
         })
         .collect();
+    let order = arrange(&candidates, &app_paths.picker);
 
-    let selection = Select::new("Select a file:", names)
-        .with_vim_mode(true)
-        .without_filtering()
-        .with_help_message("hjkl to move, enter, esc to quit")
-        .prompt_skippable()?;
+    let group_w = order
+        .iter()
+        .map(|(g, _)| g.as_deref().map_or(0, |g| g.chars().count()))
+        .max()
+        .unwrap_or(0)
+        .min(20);
+    let name_w = candidates
+        .iter()
+        .map(|c| c.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(40);
+    let title_w = candidates
+        .iter()
+        .map(|c| c.info.title.as_deref().map_or(1, |t| t.chars().count()))
+        .max()
+        .unwrap_or(0)
+        .min(40);
 
-    let selected_name = match selection {
-        Some(name) => name,
-        None => {
-            println!("Cancelled.");
-            return Ok(None);
+    order
+        .into_iter()
+        .map(|(group, i)| {
+            let c = &candidates[i];
+            let rel = Path::new(&c.name);
+            let status = publish_status(&c.path, rel, dir, app_paths);
+            let words = c.info.words.map_or("-".to_string(), |w| format!("{}w", w));
+            let mut label = format!(
+                "{:<name_w$}  {:<title_w$}  {:<10}  {:>6}  {}",
+                truncate(&c.name, name_w),
+                truncate(c.info.title.as_deref().unwrap_or("-"), title_w),
+                truncate(c.info.date.as_deref().unwrap_or("-"), 10),
+                words,
+                status,
+            );
+            if group_w > 0 {
+                let group = truncate(group.as_deref().unwrap_or_default(), group_w);
+                label = format!("{:<group_w$}  {}", group, label);
+            }
+            FileRow {
+                path: c.path.clone(),
+                label,
+            }
+        })
+        .collect()
+}
+
+/// Display order of `candidates` as (group label, index) pairs: the `pin_recent`
+/// most recently edited files first, then the rest grouped and sorted.
+fn arrange(candidates: &[Candidate], picker: &PickerConfig) -> Vec<(Option<String>, usize)> {
+    let mut by_mtime: Vec<usize> = (0..candidates.len())
+        .filter(|&i| candidates[i].modified.is_some())
+        .collect();
+    by_mtime.sort_by(|&a, &b| candidates[b].modified.cmp(&candidates[a].modified));
+    by_mtime.truncate(picker.pin_recent);
+
+    let mut sorted: Vec<usize> = (0..candidates.len())
+        .filter(|i| !by_mtime.contains(i))
+        .collect();
+    sorted.sort_by(|&a, &b| {
+        compare(&candidates[a], &candidates[b], picker.sort)
+            .then_with(|| candidates[a].name.cmp(&candidates[b].name))
+    });
+
+    let mut rest: Vec<(Option<String>, usize)> = Vec::new();
+    for i in sorted {
+        let c = &candidates[i];
+        match picker.group {
+            GroupBy::None => rest.push((None, i)),
+            GroupBy::Dir => {
+                let dir = Path::new(&c.name)
+                    .parent()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .filter(|p| !p.is_empty())
+                    .unwrap_or_else(|| ".".to_string());
+                rest.push((Some(dir), i));
+            }
+            GroupBy::Tag if c.info.tags.is_empty() => {
+                rest.push((Some("(untagged)".to_string()), i))
+            }
+            GroupBy::Tag => {
+                for tag in &c.info.tags {
+                    rest.push((Some(tag.clone()), i));
+                }
+            }
         }
-    };
+    }
+    // stable, so each group keeps the sort order
+    rest.sort_by(|a, b| a.0.cmp(&b.0));
 
-    let selected_index = content_files
-        .iter()
-        .position(|p| {
-            p.file_name()
-                .and_then(|s| s.to_str())
-                .map(|s| s == selected_name)
-                .unwrap_or(false)
-        })
-        .expect("Selected file should exist");
+    by_mtime
+        .into_iter()
+        .map(|i| (Some("recent".to_string()), i))
+        .chain(rest)
+        .collect()
+}
+
+fn compare(a: &Candidate, b: &Candidate, sort: SortBy) -> Ordering {
+    match sort {
+        SortBy::Name => a.name.cmp(&b.name),
+        // newest first, files without a time or date last
+        SortBy::Mtime => b.modified.cmp(&a.modified),
+        SortBy::Date => b.info.date.cmp(&a.info.date),
+        SortBy::Title => {
+            let key = |c: &Candidate| c.info.title.as_deref().unwrap_or(&c.name).to_lowercase();
+            key(a).cmp(&key(b))
+        }
+    }
+}
 
-    Ok(Some(content_files[selected_index].clone()))
+fn file_info(src: &str, kind: Option<Kind>) -> FileInfo {
+    match kind {
+        Some(Kind::Markdown) => {
+            let (fields, body) = match frontmatter::split(src) {
+                Some(fm) => (frontmatter::parse(fm.yaml).ok(), fm.body),
+                None => (None, src),
+            };
+            FileInfo {
+                title: fields
+                    .as_ref()
+                    .and_then(|f| frontmatter::field_str(f, "title")),
+                date: fields
+                    .as_ref()
+                    .and_then(|f| frontmatter::field_str(f, "date")),
+                words: Some(frontmatter::word_count(body)),
+                tags: fields
+                    .as_ref()
+                    .map(|f| frontmatter::field_list(f, "tags"))
+                    .unwrap_or_default(),
+            }
+        }
+        Some(Kind::Text) => FileInfo {
+            words: Some(frontmatter::word_count(src)),
+            ..Default::default()
+        },
+        _ => FileInfo::default(),
+    }
+}
+
+/// For a working file: which collections it's published in.
+/// For a published file: how it compares with its working copy.
+fn publish_status(path: &Path, rel: &Path, dir: &Path, app_paths: &AppPaths) -> String {
+    if dir == app_paths.working_files {
+        let published: Vec<&str> = app_paths
+            .collections
+            .iter()
+            .filter(|c| c.files.join(rel).exists())
+            .map(|c| c.name.as_str())
+            .collect();
+        if published.is_empty() {
+            "draft".to_string()
+        } else {
+            format!("published: {}", published.join(", "))
+        }
+    } else {
+        let working = app_paths.working_files.join(rel);
+        if !working.exists() {
+            "published, no draft".to_string()
+        } else if publish::same_file(&working, path, &app_paths.registry) {
+            "published".to_string()
+        } else {
+            "published, draft changed".to_string()
+        }
+    }
+}
+
+/// First lines of the file and the images that would go along with it.
+fn preview(path: &Path, dir: &Path, app_paths: &AppPaths) -> String {
+    let mut out = String::new();
+
+    match fs::read_to_string(path) {
+        Ok(src) => {
+            let body = match app_paths.registry.kind_of(path) {
+                Some(Kind::Markdown) => frontmatter::split(&src).map_or(src.as_str(), |fm| fm.body),
+                _ => src.as_str(),
+            };
+            let lines: Vec<&str> = body
+                .lines()
+                .skip_while(|l| l.trim().is_empty())
+                .take(PREVIEW_LINES)
+                .collect();
+            for l in lines {
+                out.push_str(&format!("{}\n", l));
+            }
+        }
+        Err(e) => out.push_str(&format!("(cannot read: {})\n", e)),
+    }
+
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let collection = app_paths.collections.iter().find(|c| c.files == dir);
+    if dir == app_paths.working_files || collection.is_some() {
+        let found = images::preview_assets(app_paths, collection, stem);
+        match found {
+            Some(files) if !files.is_empty() => {
+                let names: Vec<String> = files
+                    .iter()
+                    .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                    .collect();
+                out.push_str(&format!("images: {}\n", names.join(", ")));
+            }
+            Some(_) => out.push_str("images: none\n"),
+            None => {}
+        }
+    }
+    out
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        let mut t: String = s.chars().take(width.saturating_sub(1)).collect();
+        t.push('…');
+        t
+    }
 }
 
 pub fn list_collections(cols: Vec<CollectionPaths>) -> Result<Option<CollectionPaths>> {
@@ -104,3 +539,131 @@ pub fn list_collections(cols: Vec<CollectionPaths>) -> Result<Option<CollectionP
 
     Ok(Some(cols[selected_index].clone()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_info_reads_front_matter() {
+        let src = "---\ntitle: Hello World\ndate: 2024-05-01\n---\nOne two three.\n";
+        assert_eq!(
+            file_info(src, Some(Kind::Markdown)),
+            FileInfo {
+                title: Some("Hello World".to_string()),
+                date: Some("2024-05-01".to_string()),
+                words: Some(3),
+                tags: Vec::new(),
+            }
+        );
+        assert_eq!(file_info("just words", Some(Kind::Markdown)).words, Some(2));
+        assert_eq!(file_info("{}", Some(Kind::Json)), FileInfo::default());
+    }
+
+    fn candidate(name: &str, date: Option<&str>, tags: &[&str], age: u64) -> Candidate {
+        Candidate {
+            path: PathBuf::from(name),
+            name: name.to_string(),
+            info: FileInfo {
+                date: date.map(str::to_string),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                ..Default::default()
+            },
+            modified: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1000 - age)),
+        }
+    }
+
+    #[test]
+    fn arrange_sorts_groups_and_pins() {
+        let cands = vec![
+            candidate("a.md", Some("2024-01-01"), &["rust"], 30),
+            candidate("notes/b.md", None, &[], 10),
+            candidate("c.md", Some("2024-06-01"), &["rust", "cli"], 20),
+        ];
+        let names = |order: Vec<(Option<String>, usize)>| -> Vec<(Option<String>, String)> {
+            order
+                .into_iter()
+                .map(|(g, i)| (g, cands[i].name.clone()))
+                .collect()
+        };
+        let picker = |sort, group, pin_recent| PickerConfig {
+            sort,
+            group,
+            pin_recent,
+        };
+        let plain = |n: &[&str]| -> Vec<(Option<String>, String)> {
+            n.iter().map(|n| (None, n.to_string())).collect()
+        };
+
+        let by_date = arrange(&cands, &picker(SortBy::Date, GroupBy::None, 0));
+        assert_eq!(names(by_date), plain(&["c.md", "a.md", "notes/b.md"]));
+        let by_mtime = arrange(&cands, &picker(SortBy::Mtime, GroupBy::None, 0));
+        assert_eq!(names(by_mtime), plain(&["notes/b.md", "c.md", "a.md"]));
+
+        let pinned = arrange(&cands, &picker(SortBy::Name, GroupBy::Dir, 1));
+        let g = |g: &str, n: &str| (Some(g.to_string()), n.to_string());
+        assert_eq!(
+            names(pinned),
+            [g("recent", "notes/b.md"), g(".", "a.md"), g(".", "c.md")]
+        );
+
+        let tags = arrange(&cands, &picker(SortBy::Name, GroupBy::Tag, 0));
+        assert_eq!(
+            names(tags),
+            [
+                g("(untagged)", "notes/b.md"),
+                g("cli", "c.md"),
+                g("rust", "a.md"),
+                g("rust", "c.md")
+            ]
+        );
+    }
+
+    #[test]
+    fn typed_letters_filter_in_order() {
+        assert!(fuzzy_score("posts/Hello-World.md  Hello", "hw").is_some());
+        assert!(fuzzy_score("hello.md", "HEL md").is_some());
+        assert!(fuzzy_score("hello.md", "").is_some());
+        assert!(fuzzy_score("hello.md", "wh").is_none());
+    }
+
+    #[test]
+    fn closer_matches_rank_higher() {
+        let rank = |labels: &[&str], query: &str| -> Vec<String> {
+            ranked(labels.iter().copied(), query)
+                .into_iter()
+                .map(|i| labels[i].to_string())
+                .collect()
+        };
+        assert_eq!(
+            rank(
+                &[
+                    "pages/about-this-site.md",
+                    "drafts/old-notes.md",
+                    "posts/first.md"
+                ],
+                "post"
+            ),
+            ["posts/first.md", "pages/about-this-site.md"]
+        );
+        // word starts beat letters in the middle of words
+        assert_eq!(
+            rank(&["shower-thoughts.md", "hello-world.md"], "hw"),
+            ["hello-world.md", "shower-thoughts.md"]
+        );
+        // so do camel-case humps; ties keep their order
+        assert_eq!(
+            rank(
+                &["my-GoodNews.md", "program-genius.md", "GoodNews.md"],
+                "gn"
+            ),
+            ["my-GoodNews.md", "GoodNews.md", "program-genius.md"]
+        );
+    }
+
+    #[test]
+    fn truncate_adds_ellipsis() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("a long title", 6), "a lon…");
+    }
+}